@@ -0,0 +1,276 @@
+use std::env;
+use std::ffi::OsStr;
+use std::fmt;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+use crate::finder::Finder;
+
+/// Problems found in a path list such as `PATH`.
+///
+/// # Example
+///
+/// ```no_run
+/// # tokio_test::block_on(async {
+/// use async_which::{PathAudit, Severity};
+///
+/// let audit = PathAudit::system().await;
+/// for finding in audit.at_least(Severity::Warning) {
+///     println!("{}", finding);
+/// }
+/// # })
+/// ```
+#[derive(Clone, Debug)]
+pub struct PathAudit {
+    /// The entries of the audited path list, in order.
+    pub entries: Vec<PathBuf>,
+    /// Every problem found, ordered by entry.
+    pub findings: Vec<Finding>,
+}
+
+impl PathAudit {
+    /// Audits the entries of `paths`, split the same way a lookup would split them.
+    pub async fn new<U: AsRef<OsStr>>(paths: U) -> PathAudit {
+        let entries = Finder::path_split(paths);
+
+        let mut findings = Vec::new();
+        let mut canonical: Vec<Option<PathBuf>> = Vec::with_capacity(entries.len());
+        for (index, entry) in entries.iter().enumerate() {
+            let mut report = |issue| {
+                findings.push(Finding {
+                    index,
+                    entry: entry.clone(),
+                    issue,
+                })
+            };
+
+            if entry.as_os_str().is_empty() {
+                report(PathIssue::Empty);
+                canonical.push(None);
+                continue;
+            }
+            if is_current_dir(entry) {
+                report(PathIssue::CurrentDir);
+            } else if entry.is_relative() {
+                report(PathIssue::Relative);
+            }
+
+            if let Some(of) = entries[..index].iter().position(|e| e == entry) {
+                // Everything else was already reported against the first occurrence.
+                report(PathIssue::Duplicate { of });
+                canonical.push(canonical[of].clone());
+                continue;
+            }
+
+            let resolved = tokio::fs::canonicalize(entry).await.ok();
+            if let Some(resolved) = &resolved {
+                let of = canonical.iter().position(|c| c.as_ref() == Some(resolved));
+                if let Some(of) = of {
+                    report(PathIssue::SymlinkDuplicate {
+                        of,
+                        canonical: resolved.clone(),
+                    });
+                }
+            }
+            canonical.push(resolved);
+
+            match tokio::fs::metadata(entry).await {
+                Ok(metadata) if !metadata.is_dir() => report(PathIssue::NotADirectory),
+                Ok(metadata) => {
+                    for issue in ownership_issues(&metadata) {
+                        report(issue);
+                    }
+                }
+                Err(error) if error.kind() == io::ErrorKind::NotFound => report(PathIssue::Missing),
+                Err(error) => report(PathIssue::Io(error.kind())),
+            }
+        }
+
+        PathAudit { entries, findings }
+    }
+
+    /// Audits the `PATH` environment variable. The audit is empty if it is unset, so there is
+    /// nothing to report rather than a bogus finding for an empty entry.
+    pub async fn system() -> PathAudit {
+        PathAudit::env_var("PATH").await
+    }
+
+    /// Audits the path list in the env variable `var`, e.g. `LD_LIBRARY_PATH`. Empty if it is
+    /// unset.
+    pub async fn env_var(var: &str) -> PathAudit {
+        match env::var_os(var) {
+            Some(paths) => PathAudit::new(paths).await,
+            None => PathAudit {
+                entries: Vec::new(),
+                findings: Vec::new(),
+            },
+        }
+    }
+
+    /// The highest severity among the findings, or `None` if there are none.
+    pub fn worst(&self) -> Option<Severity> {
+        self.findings.iter().map(Finding::severity).max()
+    }
+
+    /// The findings of at least `severity`.
+    pub fn at_least(&self, severity: Severity) -> impl Iterator<Item = &Finding> {
+        self.findings
+            .iter()
+            .filter(move |finding| finding.severity() >= severity)
+    }
+
+    /// The findings for the entry at `index`.
+    pub fn findings_for(&self, index: usize) -> impl Iterator<Item = &Finding> {
+        self.findings
+            .iter()
+            .filter(move |finding| finding.index == index)
+    }
+}
+
+/// How much a [`PathIssue`] matters.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// Harmless, but likely a leftover worth cleaning up.
+    Info,
+    /// Likely to make lookups behave unexpectedly.
+    Warning,
+    /// Lets another user, or the current directory, choose which binary runs.
+    Error,
+}
+
+/// A problem with one entry of a path list.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Finding {
+    /// Index of the entry in [`PathAudit::entries`].
+    pub index: usize,
+    pub entry: PathBuf,
+    pub issue: PathIssue,
+}
+
+impl Finding {
+    pub fn severity(&self) -> Severity {
+        self.issue.severity()
+    }
+}
+
+/// What is wrong with a path list entry.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PathIssue {
+    /// The entry is empty, which shells treat as the current directory.
+    Empty,
+    /// The entry is `.`.
+    CurrentDir,
+    /// The entry is relative, so what it refers to depends on the current directory.
+    Relative,
+    /// Nothing exists at the entry.
+    Missing,
+    /// The entry exists but is not a directory.
+    NotADirectory,
+    /// The entry is identical to the entry at index `of`.
+    Duplicate { of: usize },
+    /// The entry resolves to `canonical`, as does the entry at index `of`, e.g. `/bin` and
+    /// `/usr/bin` on usr-merged systems.
+    SymlinkDuplicate { of: usize, canonical: PathBuf },
+    /// Any user can add files to the directory. `mode` holds its permission bits.
+    WorldWritable { mode: u32 },
+    /// The directory is owned by `uid`, which is neither root nor the current user.
+    ForeignOwner { uid: u32 },
+    /// Inspecting the entry failed.
+    Io(io::ErrorKind),
+}
+
+impl PathIssue {
+    pub fn severity(&self) -> Severity {
+        match self {
+            PathIssue::Empty | PathIssue::CurrentDir | PathIssue::WorldWritable { .. } => {
+                Severity::Error
+            }
+            PathIssue::Relative
+            | PathIssue::NotADirectory
+            | PathIssue::ForeignOwner { .. }
+            | PathIssue::Io(_) => Severity::Warning,
+            PathIssue::Missing
+            | PathIssue::Duplicate { .. }
+            | PathIssue::SymlinkDuplicate { .. } => Severity::Info,
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Info => write!(f, "info"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+impl fmt::Display for PathIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PathIssue::Empty => write!(f, "empty entry searches the current directory"),
+            PathIssue::CurrentDir => write!(f, "searches the current directory"),
+            PathIssue::Relative => write!(f, "is relative to the current directory"),
+            PathIssue::Missing => write!(f, "does not exist"),
+            PathIssue::NotADirectory => write!(f, "is not a directory"),
+            PathIssue::Duplicate { of } => write!(f, "duplicates entry {}", of),
+            PathIssue::SymlinkDuplicate { of, canonical } => {
+                write!(f, "resolves to {}, like entry {}", canonical.display(), of)
+            }
+            PathIssue::WorldWritable { mode } => write!(f, "is world-writable (mode {:04o})", mode),
+            PathIssue::ForeignOwner { uid } => write!(f, "is owned by uid {}", uid),
+            PathIssue::Io(kind) => write!(f, "cannot be inspected ({})", io::Error::from(*kind)),
+        }
+    }
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "[{}] {}: {}: {}",
+            self.index,
+            self.entry.display(),
+            self.severity(),
+            self.issue
+        )
+    }
+}
+
+impl fmt::Display for PathAudit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for finding in &self.findings {
+            writeln!(f, "{}", finding)?;
+        }
+        Ok(())
+    }
+}
+
+fn is_current_dir(entry: &Path) -> bool {
+    entry
+        .components()
+        .all(|component| component == Component::CurDir)
+}
+
+#[cfg(unix)]
+fn ownership_issues(metadata: &std::fs::Metadata) -> Vec<PathIssue> {
+    use std::os::unix::fs::MetadataExt;
+
+    let mut issues = Vec::new();
+    let mode = metadata.mode() & 0o7777;
+    if mode & 0o002 != 0 {
+        issues.push(PathIssue::WorldWritable { mode });
+    }
+    let uid = metadata.uid();
+    if uid != 0 && uid != unsafe { libc::geteuid() } {
+        issues.push(PathIssue::ForeignOwner { uid });
+    }
+    issues
+}
+
+#[cfg(not(unix))]
+fn ownership_issues(_metadata: &std::fs::Metadata) -> Vec<PathIssue> {
+    Vec::new()
+}
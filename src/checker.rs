@@ -1,36 +1,270 @@
-use crate::finder::Checker;
-use futures::{stream::futures_unordered::FuturesUnordered, StreamExt};
+//! Acceptance rules for candidate paths.
+//!
+//! Every lookup runs its candidates through a [`CompositeChecker`], which by default holds an
+//! [`ExistedChecker`] and an [`ExecutableChecker`]. Extra rules can be added with
+//! [`WhichConfig::add_checker`](crate::WhichConfig::add_checker) or the `*_with_checker`
+//! functions, and built from closures with [`checker_fn`] and combined with [`CheckerExt`].
+//!
+//! # Example
+//!
+//! ```no_run
+//! # tokio_test::block_on(async {
+//! use async_which::checker::{checker_fn, CheckerExt};
+//!
+//! let not_in_tmp = checker_fn(|p| async move { p.starts_with("/tmp") }).not();
+//! let result = async_which::which_with_checker("rustc", not_in_tmp).await;
+//! # })
+//! ```
+
+#[cfg(feature = "checksum")]
+pub use crate::checksum::ChecksumChecker;
+pub use crate::symlink::{SymlinkChecker, SymlinkPolicy};
+pub use crate::verdict::{Rejection, Verdict};
+#[cfg(feature = "version")]
+pub use crate::version::VersionChecker;
+
+use crate::format::{binary_kind, read_header, BinaryKind, ElfArch, ELF_HEADER_LEN};
+use crate::report::CheckOutcome;
+use crate::shebang::interpreter_chain_in;
+use futures::future;
+use std::collections::HashMap;
 #[cfg(any(unix, target_os = "wasi"))]
 use std::ffi::CString;
+use std::ffi::OsString;
+use std::future::Future;
 #[cfg(unix)]
 use std::os::unix::ffi::OsStrExt;
 #[cfg(target_os = "wasi")]
 use std::os::wasi::ffi::OsStrExt;
-use std::{future, iter::FromIterator, path::Path};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::{io, path::Path};
+
+/// Decides whether a candidate path is an acceptable result.
+#[async_trait::async_trait]
+pub trait Checker: Send + Sync {
+    async fn is_valid(&self, path: &Path) -> bool;
+
+    /// Checks `path`, explaining why it was rejected.
+    ///
+    /// Defaults to [`Self::is_valid`] with [`Rejection::Unspecified`] as the reason.
+    async fn check(&self, path: &Path) -> Verdict {
+        self.is_valid(path).await.into()
+    }
+
+    /// A short name identifying this checker in reports.
+    fn name(&self) -> &str {
+        std::any::type_name::<Self>()
+    }
+
+    /// A rough estimate of how expensive this checker is to run, used by
+    /// [`Evaluation::CostOrdered`]. Checkers that only inspect metadata cost 1, the default.
+    fn cost(&self) -> u32 {
+        1
+    }
+}
+
+#[async_trait::async_trait]
+impl<C: Checker + ?Sized> Checker for Box<C> {
+    async fn is_valid(&self, path: &Path) -> bool {
+        (**self).is_valid(path).await
+    }
+
+    async fn check(&self, path: &Path) -> Verdict {
+        (**self).check(path).await
+    }
+
+    fn name(&self) -> &str {
+        (**self).name()
+    }
+
+    fn cost(&self) -> u32 {
+        (**self).cost()
+    }
+}
+
+#[async_trait::async_trait]
+impl<C: Checker + ?Sized> Checker for Arc<C> {
+    async fn is_valid(&self, path: &Path) -> bool {
+        (**self).is_valid(path).await
+    }
 
-pub struct ExecutableChecker;
+    async fn check(&self, path: &Path) -> Verdict {
+        (**self).check(path).await
+    }
+
+    fn name(&self) -> &str {
+        (**self).name()
+    }
+
+    fn cost(&self) -> u32 {
+        (**self).cost()
+    }
+}
+
+/// Which identity an [`ExecutableChecker`] asks about.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AccessMode {
+    /// Ask the OS with `access(2)`, which uses the real user and group IDs.
+    #[default]
+    Real,
+    /// Ask the OS with `faccessat(2)` and `AT_EACCESS`, which uses the effective user and group
+    /// IDs like `execve(2)` does. Use this in setuid, setgid or otherwise elevated processes.
+    Effective,
+    /// Evaluate the permission bits against the effective user ID, effective group ID and
+    /// supplementary groups without asking the OS. ACLs, mount options and security modules
+    /// are not consulted.
+    Metadata,
+}
+
+/// Accepts files the current process may execute.
+#[derive(Default)]
+pub struct ExecutableChecker {
+    access: AccessMode,
+}
 
 impl ExecutableChecker {
     pub fn new() -> ExecutableChecker {
-        ExecutableChecker
+        ExecutableChecker::default()
+    }
+
+    /// Sets which identity executability is checked for. [`AccessMode::Real`] by default.
+    ///
+    /// Only affects Unix; elsewhere every mode behaves like [`AccessMode::Real`].
+    pub fn access_mode(mut self, access: AccessMode) -> Self {
+        self.access = access;
+        self
     }
 }
 
 #[async_trait::async_trait]
 impl Checker for ExecutableChecker {
-    #[cfg(any(unix, target_os = "wasi"))]
     async fn is_valid(&self, path: &Path) -> bool {
-        CString::new(path.as_os_str().as_bytes())
-            .map(|c| unsafe { libc::access(c.as_ptr(), libc::X_OK) == 0 })
-            .unwrap_or(false)
+        self.check(path).await.is_accepted()
+    }
+
+    #[cfg(any(unix, target_os = "wasi"))]
+    async fn check(&self, path: &Path) -> Verdict {
+        #[cfg(unix)]
+        if self.access == AccessMode::Metadata {
+            return metadata_verdict(path).await;
+        }
+
+        let c = match CString::new(path.as_os_str().as_bytes()) {
+            Ok(c) => c,
+            Err(_) => return Verdict::Rejected(Rejection::InvalidName),
+        };
+        if access(&c, self.access) == 0 {
+            return Verdict::Accepted;
+        }
+
+        let error = io::Error::last_os_error();
+        if error.kind() == io::ErrorKind::NotFound {
+            return Verdict::Rejected(Rejection::Missing);
+        }
+        Verdict::Rejected(Rejection::NotExecutable {
+            mode: file_mode(path).await,
+            errno: error.raw_os_error(),
+        })
     }
 
     #[cfg(windows)]
-    async fn is_valid(&self, _path: &Path) -> bool {
-        true
+    async fn check(&self, _path: &Path) -> Verdict {
+        Verdict::Accepted
+    }
+
+    fn name(&self) -> &str {
+        "executable"
     }
 }
 
+#[cfg(unix)]
+fn access(path: &CString, access: AccessMode) -> libc::c_int {
+    match access {
+        AccessMode::Effective => unsafe {
+            libc::faccessat(libc::AT_FDCWD, path.as_ptr(), libc::X_OK, libc::AT_EACCESS)
+        },
+        AccessMode::Real | AccessMode::Metadata => unsafe {
+            libc::access(path.as_ptr(), libc::X_OK)
+        },
+    }
+}
+
+#[cfg(target_os = "wasi")]
+fn access(path: &CString, _access: AccessMode) -> libc::c_int {
+    unsafe { libc::access(path.as_ptr(), libc::X_OK) }
+}
+
+/// Decides executability from the permission bits, the way `execve(2)` does: root may execute
+/// anything with at least one execute bit, the owner is judged by the user bits, members of
+/// the file's group by the group bits and everyone else by the other bits.
+#[cfg(unix)]
+async fn metadata_verdict(path: &Path) -> Verdict {
+    use std::os::unix::fs::MetadataExt;
+
+    let metadata = match tokio::fs::metadata(path).await {
+        Ok(metadata) if metadata.is_file() => metadata,
+        Ok(metadata) => return file_verdict(&metadata),
+        Err(e) => {
+            let reason = stat_failure(e, tokio::fs::symlink_metadata(path).await);
+            return Verdict::Rejected(reason);
+        }
+    };
+
+    let mode = metadata.mode() & 0o7777;
+    let euid = unsafe { libc::geteuid() };
+    let executable = if euid == 0 {
+        mode & 0o111 != 0
+    } else if metadata.uid() == euid {
+        mode & 0o100 != 0
+    } else if in_group(metadata.gid()) {
+        mode & 0o010 != 0
+    } else {
+        mode & 0o001 != 0
+    };
+    if executable {
+        Verdict::Accepted
+    } else {
+        Verdict::Rejected(Rejection::NotExecutable {
+            mode: Some(mode),
+            errno: Some(libc::EACCES),
+        })
+    }
+}
+
+/// Whether `gid` is the effective group ID or one of the supplementary groups.
+#[cfg(unix)]
+fn in_group(gid: u32) -> bool {
+    if gid == unsafe { libc::getegid() } {
+        return true;
+    }
+    let count = unsafe { libc::getgroups(0, std::ptr::null_mut()) };
+    if count <= 0 {
+        return false;
+    }
+    let mut groups = vec![0; count as usize];
+    let count = unsafe { libc::getgroups(count, groups.as_mut_ptr()) };
+    groups.truncate(count.max(0) as usize);
+    groups.contains(&gid)
+}
+
+#[cfg(unix)]
+async fn file_mode(path: &Path) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+
+    tokio::fs::metadata(path)
+        .await
+        .ok()
+        .map(|metadata| metadata.permissions().mode() & 0o7777)
+}
+
+#[cfg(target_os = "wasi")]
+async fn file_mode(_path: &Path) -> Option<u32> {
+    None
+}
+
+/// Accepts paths pointing to a regular file.
+#[derive(Default)]
 pub struct ExistedChecker;
 
 impl ExistedChecker {
@@ -41,41 +275,400 @@ impl ExistedChecker {
 
 #[async_trait::async_trait]
 impl Checker for ExistedChecker {
-    #[cfg(target_os = "windows")]
     async fn is_valid(&self, path: &Path) -> bool {
-        tokio::fs::symlink_metadata(path)
-            .await
-            .map(|metadata| {
+        self.check(path).await.is_accepted()
+    }
+
+    #[cfg(target_os = "windows")]
+    async fn check(&self, path: &Path) -> Verdict {
+        match tokio::fs::symlink_metadata(path).await {
+            Ok(metadata) => {
                 let file_type = metadata.file_type();
-                file_type.is_file() || file_type.is_symlink()
-            })
-            .unwrap_or(false)
+                if file_type.is_file() || file_type.is_symlink() {
+                    Verdict::Accepted
+                } else if file_type.is_dir() {
+                    Verdict::Rejected(Rejection::Directory)
+                } else {
+                    Verdict::Rejected(Rejection::NotAFile)
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Verdict::Rejected(Rejection::Missing),
+            Err(e) => Verdict::Rejected(Rejection::Io(e.into())),
+        }
     }
 
     #[cfg(unix)]
-    async fn is_valid(&self, path: &Path) -> bool {
-        tokio::fs::metadata(path)
-            .await
-            .map(|metadata| metadata.is_file())
-            .unwrap_or(false)
+    async fn check(&self, path: &Path) -> Verdict {
+        match tokio::fs::metadata(path).await {
+            Ok(metadata) => file_verdict(&metadata),
+            Err(e) => Verdict::Rejected(stat_failure(e, tokio::fs::symlink_metadata(path).await)),
+        }
     }
 
     #[cfg(target_os = "wasi")]
+    async fn check(&self, path: &Path) -> Verdict {
+        match std::fs::metadata(path) {
+            Ok(metadata) => file_verdict(&metadata),
+            Err(e) => Verdict::Rejected(stat_failure(e, std::fs::symlink_metadata(path))),
+        }
+    }
+
+    fn name(&self) -> &str {
+        "existed"
+    }
+}
+
+#[cfg(any(unix, target_os = "wasi"))]
+fn file_verdict(metadata: &std::fs::Metadata) -> Verdict {
+    if metadata.is_file() {
+        Verdict::Accepted
+    } else if metadata.is_dir() {
+        Verdict::Rejected(Rejection::Directory)
+    } else {
+        Verdict::Rejected(Rejection::NotAFile)
+    }
+}
+
+/// Why a candidate could not be stat-ed, given the outcome of `lstat` on it. Unless the
+/// candidate itself exists, e.g. as a broken link, it is missing: a directory above it may be a
+/// regular file or one that cannot be searched, but nothing is there.
+#[cfg(any(unix, target_os = "wasi"))]
+fn stat_failure(error: io::Error, symlink_metadata: io::Result<std::fs::Metadata>) -> Rejection {
+    match symlink_metadata {
+        Ok(_) if error.kind() == io::ErrorKind::NotFound => Rejection::BrokenSymlink,
+        Ok(_) => Rejection::Io(error.into()),
+        Err(_) => Rejection::Missing,
+    }
+}
+
+/// Accepts files that only trusted users can replace.
+///
+/// A candidate is rejected if the file, or any directory above it or above the file it
+/// resolves to, is world-writable (directories are allowed the sticky bit, as on `/tmp`) or is
+/// owned by a user other than root, the effective user or an explicitly trusted user.
+///
+/// Every candidate is accepted on platforms without Unix permissions.
+#[derive(Clone, Debug)]
+pub struct SecureChecker {
+    trusted_uids: Vec<u32>,
+}
+
+impl Default for SecureChecker {
+    fn default() -> Self {
+        SecureChecker::new()
+    }
+}
+
+impl SecureChecker {
+    pub fn new() -> SecureChecker {
+        #[cfg(unix)]
+        let trusted_uids = vec![0, unsafe { libc::geteuid() }];
+        #[cfg(not(unix))]
+        let trusted_uids = Vec::new();
+        SecureChecker { trusted_uids }
+    }
+
+    /// Also trusts files and directories owned by `uid`, e.g. a dedicated package manager user.
+    pub fn trust_uid(mut self, uid: u32) -> Self {
+        self.trusted_uids.push(uid);
+        self
+    }
+
+    #[cfg(unix)]
+    async fn check_owner_and_mode(&self, path: &Path, is_dir: bool) -> Option<Rejection> {
+        use std::os::unix::fs::MetadataExt;
+
+        let metadata = match tokio::fs::metadata(path).await {
+            Ok(metadata) => metadata,
+            Err(e) => return Some(Rejection::Io(e.into())),
+        };
+        let mode = metadata.mode() & 0o7777;
+        let sticky = is_dir && mode & 0o1000 != 0;
+        if mode & 0o002 != 0 && !sticky {
+            return Some(Rejection::WorldWritable {
+                path: path.to_path_buf(),
+                mode,
+            });
+        }
+        if !self.trusted_uids.contains(&metadata.uid()) {
+            return Some(Rejection::UntrustedOwner {
+                path: path.to_path_buf(),
+                uid: metadata.uid(),
+            });
+        }
+        None
+    }
+}
+
+#[async_trait::async_trait]
+impl Checker for SecureChecker {
+    async fn is_valid(&self, path: &Path) -> bool {
+        self.check(path).await.is_accepted()
+    }
+
+    #[cfg(unix)]
+    async fn check(&self, path: &Path) -> Verdict {
+        let canonical = match tokio::fs::canonicalize(path).await {
+            Ok(canonical) => canonical,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                return Verdict::Rejected(Rejection::Missing)
+            }
+            Err(e) => return Verdict::Rejected(Rejection::Io(e.into())),
+        };
+        if let Some(reason) = self.check_owner_and_mode(&canonical, false).await {
+            return Verdict::Rejected(reason);
+        }
+
+        // Symlinks in the original path can be swapped by whoever owns the directory holding
+        // them, so both the path as given and the resolved path must be trustworthy.
+        let mut directories: Vec<&Path> = Vec::new();
+        for dir in path
+            .ancestors()
+            .skip(1)
+            .chain(canonical.ancestors().skip(1))
+        {
+            if !dir.as_os_str().is_empty() && !directories.contains(&dir) {
+                directories.push(dir);
+            }
+        }
+        for dir in directories {
+            if let Some(reason) = self.check_owner_and_mode(dir, true).await {
+                return Verdict::Rejected(reason);
+            }
+        }
+        Verdict::Accepted
+    }
+
+    #[cfg(not(unix))]
+    async fn check(&self, _path: &Path) -> Verdict {
+        Verdict::Accepted
+    }
+
+    fn name(&self) -> &str {
+        "secure"
+    }
+
+    fn cost(&self) -> u32 {
+        4
+    }
+}
+
+/// Accepts files whose header identifies a known executable format.
+///
+/// Empty and unrecognized files are rejected, which catches half-written downloads that still
+/// carry the execute bit. Scripts without a `#!` line, including Windows batch files, are
+/// unrecognized too; use [`Self::allow_unknown`] to accept them.
+#[derive(Clone, Debug, Default)]
+pub struct FormatChecker {
+    require_binary: bool,
+    allow_unknown: bool,
+}
+
+impl FormatChecker {
+    pub fn new() -> FormatChecker {
+        FormatChecker::default()
+    }
+
+    /// Whether to reject `#!` scripts and accept only compiled binaries. `false` by default.
+    pub fn require_binary(mut self, require: bool) -> Self {
+        self.require_binary = require;
+        self
+    }
+
+    /// Whether to accept files of unrecognized format. `false` by default.
+    pub fn allow_unknown(mut self, allow: bool) -> Self {
+        self.allow_unknown = allow;
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl Checker for FormatChecker {
+    async fn is_valid(&self, path: &Path) -> bool {
+        self.check(path).await.is_accepted()
+    }
+
+    async fn check(&self, path: &Path) -> Verdict {
+        let kind = match binary_kind(path).await {
+            Ok(kind) => kind,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                return Verdict::Rejected(Rejection::Missing)
+            }
+            Err(e) => return Verdict::Rejected(Rejection::Io(e.into())),
+        };
+        let accepted = match kind {
+            BinaryKind::Empty => false,
+            BinaryKind::Unknown => self.allow_unknown && !self.require_binary,
+            BinaryKind::Script => !self.require_binary,
+            _ => true,
+        };
+        if accepted {
+            Verdict::Accepted
+        } else {
+            Verdict::Rejected(Rejection::UnexpectedFormat(kind))
+        }
+    }
+
+    fn name(&self) -> &str {
+        "format"
+    }
+
+    fn cost(&self) -> u32 {
+        2
+    }
+}
+
+/// Accepts ELF binaries built for a given architecture.
+///
+/// Files that are not ELF binaries, such as scripts, are accepted; combine with a
+/// [`FormatChecker`] to reject those.
+#[derive(Clone, Debug)]
+pub struct ArchChecker {
+    target: Option<ElfArch>,
+}
+
+impl Default for ArchChecker {
+    fn default() -> Self {
+        ArchChecker::host()
+    }
+}
+
+impl ArchChecker {
+    /// Accepts binaries for `target`.
+    pub fn new(target: ElfArch) -> ArchChecker {
+        ArchChecker {
+            target: Some(target),
+        }
+    }
+
+    /// Accepts binaries the running platform can execute natively. Accepts every binary if the
+    /// host architecture is not known to this crate.
+    pub fn host() -> ArchChecker {
+        ArchChecker {
+            target: ElfArch::host(),
+        }
+    }
+
+    /// Accepts binaries for a target triple such as `aarch64-unknown-linux-gnu`, or returns
+    /// `None` if its architecture is not known to this crate.
+    pub fn for_target(triple: &str) -> Option<ArchChecker> {
+        ElfArch::for_target(triple).map(ArchChecker::new)
+    }
+}
+
+#[async_trait::async_trait]
+impl Checker for ArchChecker {
+    async fn is_valid(&self, path: &Path) -> bool {
+        self.check(path).await.is_accepted()
+    }
+
+    async fn check(&self, path: &Path) -> Verdict {
+        let expected = match self.target {
+            Some(expected) => expected,
+            None => return Verdict::Accepted,
+        };
+        let mut header = [0; ELF_HEADER_LEN];
+        let header = match read_header(path, &mut header).await {
+            Ok(len) => &header[..len],
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                return Verdict::Rejected(Rejection::Missing)
+            }
+            Err(e) => return Verdict::Rejected(Rejection::Io(e.into())),
+        };
+        if BinaryKind::from_header(header) != BinaryKind::Elf {
+            return Verdict::Accepted;
+        }
+        // A truncated header, or one with a bad class or byte order, is not a usable ELF file.
+        match ElfArch::from_header(header) {
+            Some(found) if found == expected => Verdict::Accepted,
+            Some(found) => Verdict::Rejected(Rejection::WrongArchitecture { expected, found }),
+            None => Verdict::Rejected(Rejection::UnexpectedFormat(BinaryKind::Elf)),
+        }
+    }
+
+    fn name(&self) -> &str {
+        "arch"
+    }
+
+    fn cost(&self) -> u32 {
+        2
+    }
+}
+
+/// Accepts scripts whose `#!` interpreter chain ends in an executable binary.
+///
+/// Programs run through `env` are looked up in `PATH` at check time, or in the list given to
+/// [`Self::path_list`]. Files that are not scripts are accepted.
+#[derive(Clone, Debug, Default)]
+pub struct ShebangChecker {
+    path_list: Option<OsString>,
+}
+
+impl ShebangChecker {
+    pub fn new() -> ShebangChecker {
+        ShebangChecker::default()
+    }
+
+    /// Looks up programs run through `env` in `path_list` instead of `PATH`.
+    pub fn path_list(mut self, path_list: OsString) -> Self {
+        self.path_list = Some(path_list);
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl Checker for ShebangChecker {
     async fn is_valid(&self, path: &Path) -> bool {
-        std::fs::metadata(path)
-            .map(|metadata| metadata.is_file())
-            .unwrap_or(false)
+        self.check(path).await.is_accepted()
+    }
+
+    async fn check(&self, path: &Path) -> Verdict {
+        let paths = self.path_list.clone().or_else(|| std::env::var_os("PATH"));
+        match interpreter_chain_in(path, paths).await {
+            Ok(Some(chain)) => match chain.rejection() {
+                Some(reason) => Verdict::Rejected(reason),
+                None => Verdict::Accepted,
+            },
+            Ok(None) => Verdict::Accepted,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Verdict::Rejected(Rejection::Missing),
+            Err(e) => Verdict::Rejected(Rejection::Io(e.into())),
+        }
+    }
+
+    fn name(&self) -> &str {
+        "shebang"
+    }
+
+    fn cost(&self) -> u32 {
+        3
     }
 }
 
+/// How a [`CompositeChecker`] runs its checkers.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Evaluation {
+    /// One at a time in declaration order, stopping at the first rejection.
+    #[default]
+    Sequential,
+    /// All at once, waiting for every checker to finish.
+    Concurrent,
+    /// One at a time from the lowest to the highest [`Checker::cost`], stopping at the first
+    /// rejection. Checkers of equal cost keep their declaration order.
+    CostOrdered,
+}
+
+/// Accepts a path only if every contained checker accepts it.
+#[derive(Default)]
 pub struct CompositeChecker {
     checkers: Vec<Box<dyn Checker>>,
+    evaluation: Evaluation,
 }
 
 impl CompositeChecker {
     pub fn new() -> CompositeChecker {
         CompositeChecker {
             checkers: Vec::new(),
+            evaluation: Evaluation::default(),
         }
     }
 
@@ -83,12 +676,434 @@ impl CompositeChecker {
         self.checkers.push(checker);
         self
     }
+
+    /// Sets how checkers are run. [`Evaluation::Sequential`] by default.
+    pub fn evaluation(mut self, evaluation: Evaluation) -> CompositeChecker {
+        self.evaluation = evaluation;
+        self
+    }
+
+    /// Runs the checkers against `path`, recording the outcome of each one that ran.
+    ///
+    /// Outcomes are listed in the order the checkers ran; checkers skipped after a rejection
+    /// are omitted.
+    pub async fn explain(&self, path: &Path) -> Vec<CheckOutcome> {
+        match self.evaluation {
+            Evaluation::Concurrent => {
+                let jobs = self.checkers.iter().map(|checker| async move {
+                    CheckOutcome {
+                        checker: checker.name().to_owned(),
+                        verdict: run_checker(checker.as_ref(), path).await,
+                    }
+                });
+                future::join_all(jobs).await
+            }
+            Evaluation::Sequential | Evaluation::CostOrdered => {
+                let mut outcomes = Vec::new();
+                for checker in self.ordered() {
+                    let verdict = run_checker(checker, path).await;
+                    let rejected = !verdict.is_accepted();
+                    outcomes.push(CheckOutcome {
+                        checker: checker.name().to_owned(),
+                        verdict,
+                    });
+                    if rejected {
+                        break;
+                    }
+                }
+                outcomes
+            }
+        }
+    }
+
+    fn ordered(&self) -> Vec<&dyn Checker> {
+        let mut checkers: Vec<&dyn Checker> = self.checkers.iter().map(|c| c.as_ref()).collect();
+        if self.evaluation == Evaluation::CostOrdered {
+            checkers.sort_by_key(|checker| checker.cost());
+        }
+        checkers
+    }
 }
 
 #[async_trait::async_trait]
 impl Checker for CompositeChecker {
     async fn is_valid(&self, path: &Path) -> bool {
-        let jobs = self.checkers.iter().map(|checker| checker.is_valid(path));
-        FuturesUnordered::from_iter(jobs).all(future::ready).await
+        self.check(path).await.is_accepted()
+    }
+
+    /// Reports the first rejection, in declaration order when running concurrently.
+    async fn check(&self, path: &Path) -> Verdict {
+        match self.evaluation {
+            Evaluation::Concurrent => {
+                let jobs = self
+                    .checkers
+                    .iter()
+                    .map(|checker| run_checker(checker.as_ref(), path));
+                future::join_all(jobs)
+                    .await
+                    .into_iter()
+                    .find(|verdict| !verdict.is_accepted())
+                    .unwrap_or(Verdict::Accepted)
+            }
+            Evaluation::Sequential | Evaluation::CostOrdered => {
+                for checker in self.ordered() {
+                    let verdict = run_checker(checker, path).await;
+                    if !verdict.is_accepted() {
+                        return verdict;
+                    }
+                }
+                Verdict::Accepted
+            }
+        }
+    }
+
+    fn name(&self) -> &str {
+        "composite"
+    }
+
+    fn cost(&self) -> u32 {
+        self.checkers.iter().map(|checker| checker.cost()).sum()
+    }
+}
+
+/// Runs a single checker, tracing its verdict and how long it took.
+async fn run_checker(checker: &dyn Checker, path: &Path) -> Verdict {
+    #[cfg(feature = "tracing")]
+    let start = std::time::Instant::now();
+    let verdict = checker.check(path).await;
+    trace_event!(
+        TRACE,
+        checker = checker.name(),
+        path = %path.display(),
+        verdict = %verdict,
+        elapsed = ?start.elapsed(),
+        "checked candidate"
+    );
+    verdict
+}
+
+/// Combinators available on every [`Checker`].
+pub trait CheckerExt: Checker + Sized {
+    /// Accepts a path only if both `self` and `other` accept it. `other` only runs if `self`
+    /// accepted.
+    fn and<C: Checker>(self, other: C) -> And<Self, C> {
+        let name = format!("({} and {})", self.name(), other.name());
+        And {
+            first: self,
+            second: other,
+            name,
+        }
+    }
+
+    /// Accepts a path if either `self` or `other` accepts it. `other` only runs if `self`
+    /// rejected.
+    fn or<C: Checker>(self, other: C) -> Or<Self, C> {
+        let name = format!("({} or {})", self.name(), other.name());
+        Or {
+            first: self,
+            second: other,
+            name,
+        }
+    }
+
+    /// Accepts exactly the paths `self` rejects.
+    fn not(self) -> Not<Self> {
+        let name = format!("not {}", self.name());
+        Not { inner: self, name }
+    }
+
+    /// Remembers the verdicts of `self` until the file they were given for changes. See
+    /// [`CachedChecker`].
+    fn cached(self) -> CachedChecker<Self> {
+        CachedChecker::new(self)
+    }
+}
+
+impl<C: Checker> CheckerExt for C {}
+
+/// See [`CheckerExt::and`].
+pub struct And<A, B> {
+    first: A,
+    second: B,
+    name: String,
+}
+
+#[async_trait::async_trait]
+impl<A: Checker, B: Checker> Checker for And<A, B> {
+    async fn is_valid(&self, path: &Path) -> bool {
+        self.check(path).await.is_accepted()
+    }
+
+    async fn check(&self, path: &Path) -> Verdict {
+        match self.first.check(path).await {
+            Verdict::Accepted => self.second.check(path).await,
+            rejected => rejected,
+        }
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn cost(&self) -> u32 {
+        self.first.cost() + self.second.cost()
+    }
+}
+
+/// See [`CheckerExt::or`].
+pub struct Or<A, B> {
+    first: A,
+    second: B,
+    name: String,
+}
+
+#[async_trait::async_trait]
+impl<A: Checker, B: Checker> Checker for Or<A, B> {
+    async fn is_valid(&self, path: &Path) -> bool {
+        self.check(path).await.is_accepted()
+    }
+
+    /// Reports the first checker's rejection when both reject.
+    async fn check(&self, path: &Path) -> Verdict {
+        match self.first.check(path).await {
+            Verdict::Accepted => Verdict::Accepted,
+            rejected => match self.second.check(path).await {
+                Verdict::Accepted => Verdict::Accepted,
+                _ => rejected,
+            },
+        }
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn cost(&self) -> u32 {
+        self.first.cost() + self.second.cost()
+    }
+}
+
+/// See [`CheckerExt::not`].
+pub struct Not<C> {
+    inner: C,
+    name: String,
+}
+
+#[async_trait::async_trait]
+impl<C: Checker> Checker for Not<C> {
+    async fn is_valid(&self, path: &Path) -> bool {
+        self.check(path).await.is_accepted()
+    }
+
+    async fn check(&self, path: &Path) -> Verdict {
+        match self.inner.check(path).await {
+            Verdict::Accepted => Verdict::Rejected(Rejection::Other(format!(
+                "was accepted by {}",
+                self.inner.name()
+            ))),
+            Verdict::Rejected(_) => Verdict::Accepted,
+        }
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn cost(&self) -> u32 {
+        self.inner.cost()
+    }
+}
+
+/// Builds a checker from an async closure.
+///
+/// The closure receives an owned copy of the candidate path and returns anything convertible
+/// into a [`Verdict`], such as a `bool`.
+///
+/// ```
+/// use async_which::checker::checker_fn;
+///
+/// let small = checker_fn(|p| async move {
+///     tokio::fs::metadata(p)
+///         .await
+///         .map(|m| m.len() < 1024 * 1024)
+///         .unwrap_or(false)
+/// })
+/// .named("small");
+/// ```
+pub fn checker_fn<F, Fut, R>(f: F) -> FnChecker<F>
+where
+    F: Fn(PathBuf) -> Fut + Send + Sync,
+    Fut: Future<Output = R> + Send,
+    R: Into<Verdict>,
+{
+    FnChecker {
+        f,
+        name: "custom".to_owned(),
+        cost: 1,
+    }
+}
+
+/// See [`checker_fn`].
+pub struct FnChecker<F> {
+    f: F,
+    name: String,
+    cost: u32,
+}
+
+impl<F> FnChecker<F> {
+    /// Sets the name shown for this checker in reports.
+    pub fn named(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Sets the [`Checker::cost`] of this checker.
+    pub fn with_cost(mut self, cost: u32) -> Self {
+        self.cost = cost;
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl<F, Fut, R> Checker for FnChecker<F>
+where
+    F: Fn(PathBuf) -> Fut + Send + Sync,
+    Fut: Future<Output = R> + Send,
+    R: Into<Verdict>,
+{
+    async fn is_valid(&self, path: &Path) -> bool {
+        self.check(path).await.is_accepted()
+    }
+
+    async fn check(&self, path: &Path) -> Verdict {
+        (self.f)(path.to_path_buf()).await.into()
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn cost(&self) -> u32 {
+        self.cost
+    }
+}
+
+/// Remembers the verdicts of another checker, keyed by path and file identity.
+///
+/// A verdict is reused for as long as the file at its path has the same device, inode, size,
+/// modification time and status change time (size and modification time only, off Unix), so
+/// expensive checkers such as hashing or version probing run once per file version. Paths that
+/// cannot be inspected are never cached.
+///
+/// Clones share the same cache, so one checker can be handed to many lookups and tasks.
+///
+/// # Example
+///
+/// ```no_run
+/// # tokio_test::block_on(async {
+/// use async_which::checker::{checker_fn, CheckerExt};
+///
+/// let expensive = checker_fn(|p| async move { !p.ends_with("old") }).cached();
+/// for _ in 0..1000 {
+///     async_which::which_with_checker("protoc", expensive.clone()).await.unwrap();
+/// }
+/// # })
+/// ```
+pub struct CachedChecker<C> {
+    inner: Arc<C>,
+    cache: Arc<Mutex<HashMap<PathBuf, (FileIdentity, Verdict)>>>,
+}
+
+impl<C> Clone for CachedChecker<C> {
+    fn clone(&self) -> Self {
+        CachedChecker {
+            inner: Arc::clone(&self.inner),
+            cache: Arc::clone(&self.cache),
+        }
+    }
+}
+
+impl<C: Checker> CachedChecker<C> {
+    pub fn new(inner: C) -> CachedChecker<C> {
+        CachedChecker {
+            inner: Arc::new(inner),
+            cache: Arc::default(),
+        }
+    }
+
+    /// Forgets the verdict for `path`, if there is one.
+    pub fn invalidate(&self, path: &Path) {
+        self.lock().remove(path);
+    }
+
+    /// Forgets every verdict.
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<PathBuf, (FileIdentity, Verdict)>> {
+        // A panic while holding the lock cannot leave the map half-updated.
+        self.cache.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[async_trait::async_trait]
+impl<C: Checker> Checker for CachedChecker<C> {
+    async fn is_valid(&self, path: &Path) -> bool {
+        self.check(path).await.is_accepted()
+    }
+
+    async fn check(&self, path: &Path) -> Verdict {
+        let identity = match FileIdentity::of(path).await {
+            Some(identity) => identity,
+            None => return self.inner.check(path).await,
+        };
+        if let Some((cached, verdict)) = self.lock().get(path) {
+            if *cached == identity {
+                trace_event!(TRACE, path = %path.display(), checker = self.inner.name(), "cache hit");
+                return verdict.clone();
+            }
+        }
+
+        let verdict = self.inner.check(path).await;
+        self.lock()
+            .insert(path.to_path_buf(), (identity, verdict.clone()));
+        verdict
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn cost(&self) -> u32 {
+        self.inner.cost()
+    }
+}
+
+/// What must stay the same for a cached verdict to be reused.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct FileIdentity {
+    #[cfg(unix)]
+    dev_ino: (u64, u64),
+    size: u64,
+    modified: Option<std::time::SystemTime>,
+    #[cfg(unix)]
+    changed: (i64, i64),
+}
+
+impl FileIdentity {
+    async fn of(path: &Path) -> Option<FileIdentity> {
+        #[cfg(unix)]
+        use std::os::unix::fs::MetadataExt;
+
+        let metadata = tokio::fs::metadata(path).await.ok()?;
+        Some(FileIdentity {
+            #[cfg(unix)]
+            dev_ino: (metadata.dev(), metadata.ino()),
+            size: metadata.len(),
+            modified: metadata.modified().ok(),
+            #[cfg(unix)]
+            changed: (metadata.ctime(), metadata.ctime_nsec()),
+        })
     }
 }
@@ -0,0 +1,157 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+use sha2::{Digest, Sha256};
+use tokio::io::AsyncReadExt;
+
+use crate::checker::Checker;
+use crate::verdict::{Rejection, Verdict};
+
+/// How many bytes are hashed per read.
+const CHUNK_LEN: usize = 64 * 1024;
+
+/// A SHA-256 digest, displayed and parsed as lowercase hexadecimal.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Sha256Digest(pub [u8; 32]);
+
+impl fmt::Display for Sha256Digest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in self.0 {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Sha256Digest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Sha256Digest({})", self)
+    }
+}
+
+impl FromStr for Sha256Digest {
+    type Err = DigestParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || DigestParseError {
+            input: s.to_owned(),
+        };
+        if s.len() != 64 || !s.is_ascii() {
+            return Err(error());
+        }
+        let mut digest = [0; 32];
+        for (byte, hex) in digest.iter_mut().zip(s.as_bytes().chunks(2)) {
+            let hex = std::str::from_utf8(hex).map_err(|_| error())?;
+            *byte = u8::from_str_radix(hex, 16).map_err(|_| error())?;
+        }
+        Ok(Sha256Digest(digest))
+    }
+}
+
+/// A string that is not a hexadecimal SHA-256 digest.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DigestParseError {
+    input: String,
+}
+
+impl fmt::Display for DigestParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid SHA-256 digest {:?}", self.input)
+    }
+}
+
+impl std::error::Error for DigestParseError {}
+
+/// Hashes the file at `path` with SHA-256, reading it in chunks.
+pub async fn sha256_file<P: AsRef<Path>>(path: P) -> io::Result<Sha256Digest> {
+    let mut file = tokio::fs::File::open(path).await?;
+    let mut hasher = Sha256::new();
+    let mut chunk = vec![0; CHUNK_LEN];
+    loop {
+        match file.read(&mut chunk).await? {
+            0 => break,
+            n => hasher.update(&chunk[..n]),
+        }
+    }
+    Ok(Sha256Digest(hasher.finalize().into()))
+}
+
+/// Accepts files whose SHA-256 digest is in an allowlist, wherever they are.
+///
+/// # Example
+///
+/// ```no_run
+/// # tokio_test::block_on(async {
+/// use async_which::checker::ChecksumChecker;
+///
+/// let vetted = ChecksumChecker::from_sums_file("/etc/vetted-tools.sha256").await.unwrap();
+/// let cosign = async_which::which_with_checker("cosign", vetted).await.unwrap();
+/// # })
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ChecksumChecker {
+    allowed: HashSet<Sha256Digest>,
+}
+
+impl ChecksumChecker {
+    /// A checker that accepts nothing until digests are allowed.
+    pub fn new() -> ChecksumChecker {
+        ChecksumChecker::default()
+    }
+
+    /// Also accepts files with `digest`.
+    pub fn allow(mut self, digest: Sha256Digest) -> Self {
+        self.allowed.insert(digest);
+        self
+    }
+
+    /// Also accepts files with the digest written in hexadecimal as `digest`.
+    pub fn allow_hex(self, digest: &str) -> Result<Self, DigestParseError> {
+        Ok(self.allow(digest.parse()?))
+    }
+
+    /// Accepts the digests listed in `sums`, in the format written by `sha256sum`: one digest
+    /// per line, optionally followed by a file name, which is ignored. Blank lines and lines
+    /// starting with `#` are skipped.
+    pub fn from_sums(sums: &str) -> Result<ChecksumChecker, DigestParseError> {
+        sums.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| line.split_whitespace().next().unwrap_or(line))
+            .try_fold(ChecksumChecker::new(), ChecksumChecker::allow_hex)
+    }
+
+    /// Reads the file at `path` and accepts the digests it lists, as in [`Self::from_sums`].
+    /// Malformed lines are reported as [`io::ErrorKind::InvalidData`].
+    pub async fn from_sums_file<P: AsRef<Path>>(path: P) -> io::Result<ChecksumChecker> {
+        let sums = tokio::fs::read_to_string(path).await?;
+        ChecksumChecker::from_sums(&sums).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+#[async_trait::async_trait]
+impl Checker for ChecksumChecker {
+    async fn is_valid(&self, path: &Path) -> bool {
+        self.check(path).await.is_accepted()
+    }
+
+    async fn check(&self, path: &Path) -> Verdict {
+        match sha256_file(path).await {
+            Ok(digest) if self.allowed.contains(&digest) => Verdict::Accepted,
+            Ok(digest) => Verdict::Rejected(Rejection::UnknownChecksum(digest)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Verdict::Rejected(Rejection::Missing),
+            Err(e) => Verdict::Rejected(Rejection::Io(e.into())),
+        }
+    }
+
+    fn name(&self) -> &str {
+        "checksum"
+    }
+
+    fn cost(&self) -> u32 {
+        50
+    }
+}
@@ -1,26 +1,192 @@
+use std::ffi::OsString;
 use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+use crate::verdict::Rejection;
 
 pub type Result<T> = std::result::Result<T, Error>;
 
-#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+#[derive(Debug)]
+#[non_exhaustive]
 pub enum Error {
-    BadAbsolutePath,
-    BadRelativePath,
-    CannotFindBinaryPath,
-    CannotGetCurrentDir,
-    CannotCanonicalize,
+    /// No executable named `name` was found in any of `search_paths`.
+    CannotFindBinaryPath {
+        name: OsString,
+        search_paths: Vec<PathBuf>,
+    },
+    /// `candidate` exists but cannot be executed.
+    ///
+    /// `mode` holds the candidate's permission bits on Unix.
+    NotExecutable {
+        name: OsString,
+        candidate: PathBuf,
+        mode: Option<u32>,
+        source: io::Error,
+    },
+    /// `candidate` exists but a checker rejected it for `reason`.
+    Rejected {
+        name: OsString,
+        candidate: PathBuf,
+        reason: Rejection,
+    },
+    /// `name` has no path separator and no path list (e.g. `PATH`) was available.
+    PathUnset {
+        name: OsString,
+    },
+    /// `name` is empty or cannot be passed to the operating system, e.g. it contains an interior
+    /// NUL.
+    InvalidName {
+        name: OsString,
+    },
+    /// A directory in the search list, or one of its entries, could not be read.
+    UnreadableDirectory {
+        path: PathBuf,
+        source: io::Error,
+    },
+    CannotGetCurrentDir {
+        source: io::Error,
+    },
+    CannotCanonicalize {
+        path: PathBuf,
+        source: io::Error,
+    },
+    /// Neither `RUSTUP_HOME` nor the home directory is set.
+    #[cfg(feature = "rustup")]
+    RustupHomeUnset,
+    /// No rustup toolchain is set for `cwd`, not even a default one.
+    #[cfg(feature = "rustup")]
+    ToolchainUnset {
+        cwd: PathBuf,
+    },
+    /// The rustup toolchain `name` was chosen but is not installed in `dir`.
+    #[cfg(feature = "rustup")]
+    ToolchainNotInstalled {
+        name: String,
+        dir: PathBuf,
+    },
+    /// A rustup `settings.toml` or toolchain file could not be read or parsed.
+    #[cfg(feature = "rustup")]
+    InvalidRustupConfig {
+        path: PathBuf,
+        reason: String,
+    },
+}
+
+impl Error {
+    /// The name that was being looked up, if this error relates to a lookup.
+    pub fn name(&self) -> Option<&OsString> {
+        match self {
+            Error::CannotFindBinaryPath { name, .. }
+            | Error::NotExecutable { name, .. }
+            | Error::Rejected { name, .. }
+            | Error::PathUnset { name }
+            | Error::InvalidName { name } => Some(name),
+            Error::UnreadableDirectory { .. }
+            | Error::CannotGetCurrentDir { .. }
+            | Error::CannotCanonicalize { .. } => None,
+            #[cfg(feature = "rustup")]
+            Error::RustupHomeUnset
+            | Error::ToolchainUnset { .. }
+            | Error::ToolchainNotInstalled { .. }
+            | Error::InvalidRustupConfig { .. } => None,
+        }
+    }
 }
 
-impl std::error::Error for Error {}
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::NotExecutable { source, .. }
+            | Error::UnreadableDirectory { source, .. }
+            | Error::CannotGetCurrentDir { source }
+            | Error::CannotCanonicalize { source, .. } => Some(source),
+            Error::Rejected {
+                reason: Rejection::Io(error),
+                ..
+            } => Some(error.get_ref()),
+            _ => None,
+        }
+    }
+}
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Error::BadAbsolutePath => write!(f, "bad absolute path"),
-            Error::BadRelativePath => write!(f, "bad relative path"),
-            Error::CannotFindBinaryPath => write!(f, "cannot find binary path"),
-            Error::CannotGetCurrentDir => write!(f, "cannot get current directory"),
-            Error::CannotCanonicalize => write!(f, "cannot canonicalize path"),
+            Error::CannotFindBinaryPath { name, search_paths } => {
+                write!(f, "cannot find binary path for {:?} in [", name)?;
+                for (i, p) in search_paths.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", p.display())?;
+                }
+                write!(f, "]")
+            }
+            Error::NotExecutable {
+                name,
+                candidate,
+                mode,
+                ..
+            } => {
+                write!(
+                    f,
+                    "found {} for {:?} but it is not executable",
+                    candidate.display(),
+                    name
+                )?;
+                match mode {
+                    Some(mode) => write!(f, " (mode {:04o})", mode),
+                    None => Ok(()),
+                }
+            }
+            Error::Rejected {
+                name,
+                candidate,
+                reason,
+            } => write!(
+                f,
+                "found {} for {:?} but it {}",
+                candidate.display(),
+                name,
+                reason
+            ),
+            Error::PathUnset { name } => {
+                write!(f, "cannot search for {:?}: no path list is set", name)
+            }
+            Error::InvalidName { name } => write!(f, "invalid binary name {:?}", name),
+            Error::UnreadableDirectory { path, source } => write!(
+                f,
+                "cannot read directory {} ({:?})",
+                path.display(),
+                source.kind()
+            ),
+            Error::CannotGetCurrentDir { .. } => write!(f, "cannot get current directory"),
+            Error::CannotCanonicalize { path, .. } => {
+                write!(f, "cannot canonicalize path {}", path.display())
+            }
+            #[cfg(feature = "rustup")]
+            Error::RustupHomeUnset => write!(f, "cannot find the rustup home directory"),
+            #[cfg(feature = "rustup")]
+            Error::ToolchainUnset { cwd } => {
+                write!(f, "no rustup toolchain is set for {}", cwd.display())
+            }
+            #[cfg(feature = "rustup")]
+            Error::ToolchainNotInstalled { name, dir } => write!(
+                f,
+                "rustup toolchain {} is not installed in {}",
+                name,
+                dir.display()
+            ),
+            #[cfg(feature = "rustup")]
+            Error::InvalidRustupConfig { path, reason } => {
+                write!(
+                    f,
+                    "invalid rustup configuration {}: {}",
+                    path.display(),
+                    reason
+                )
+            }
         }
     }
 }
@@ -1,20 +1,97 @@
-use crate::checker::CompositeChecker;
+use crate::checker::{Checker, CompositeChecker};
 use crate::error::*;
 #[cfg(windows)]
 use crate::helper::has_executable_extension;
+use crate::lookup::Lookup;
+use crate::report::{Probe, SearchReport};
+use crate::shadow::{same_file, Located, Shadowed, Shadowing};
+use crate::verdict::{Rejection, Verdict};
 use either::Either;
 use futures::prelude::*;
 #[cfg(feature = "regex")]
 use regex::Regex;
-#[cfg(feature = "regex")]
-use std::borrow::Borrow;
 use std::ffi::OsStr;
+use std::io;
 use std::iter;
 use std::path::{Path, PathBuf};
 
-#[async_trait::async_trait]
-pub trait Checker: Sync {
-    async fn is_valid(&self, path: &Path) -> bool;
+/// A candidate path, along with the index of the path list entry it was built from.
+type Candidate = (Option<usize>, PathBuf);
+
+/// A list of directories to search: a string separated like the `PATH` env variable, or a
+/// [`SearchPath`](crate::SearchPath).
+pub trait PathList {
+    fn into_dirs(self) -> Vec<PathBuf>;
+}
+
+impl<T: AsRef<OsStr>> PathList for T {
+    fn into_dirs(self) -> Vec<PathBuf> {
+        Finder::path_split(self)
+    }
+}
+
+/// A pattern that [`Finder::find_re`] matches file names against.
+#[cfg(feature = "regex")]
+pub trait FileNameMatcher {
+    fn is_match_file_name(&self, file_name: &OsStr) -> bool;
+
+    fn as_str(&self) -> &str;
+}
+
+/// Only file names that are valid Unicode can match.
+#[cfg(feature = "regex")]
+impl FileNameMatcher for Regex {
+    fn is_match_file_name(&self, file_name: &OsStr) -> bool {
+        file_name.to_str().is_some_and(|name| self.is_match(name))
+    }
+
+    fn as_str(&self) -> &str {
+        Regex::as_str(self)
+    }
+}
+
+/// Matches the raw bytes of file names on Unix, and their lossy UTF-8 form elsewhere.
+#[cfg(feature = "regex")]
+impl FileNameMatcher for regex::bytes::Regex {
+    #[cfg(unix)]
+    fn is_match_file_name(&self, file_name: &OsStr) -> bool {
+        use std::os::unix::ffi::OsStrExt;
+
+        self.is_match(file_name.as_bytes())
+    }
+
+    #[cfg(not(unix))]
+    fn is_match_file_name(&self, file_name: &OsStr) -> bool {
+        self.is_match(file_name.to_string_lossy().as_bytes())
+    }
+
+    fn as_str(&self) -> &str {
+        regex::bytes::Regex::as_str(self)
+    }
+}
+
+/// Either kind of regex, as stored by [`crate::WhichConfig`].
+#[cfg(feature = "regex")]
+pub enum NamePattern {
+    Str(Regex),
+    Bytes(regex::bytes::Regex),
+}
+
+#[cfg(feature = "regex")]
+impl FileNameMatcher for NamePattern {
+    fn is_match_file_name(&self, file_name: &OsStr) -> bool {
+        match self {
+            NamePattern::Str(regex) => regex.is_match_file_name(file_name),
+            NamePattern::Bytes(regex) => regex.is_match_file_name(file_name),
+        }
+    }
+
+    fn as_str(&self) -> &str {
+        match self {
+            NamePattern::Str(regex) => regex.as_str(),
+            NamePattern::Bytes(regex) => regex.as_str(),
+        }
+    }
 }
 
 trait PathExt {
@@ -52,7 +129,7 @@ impl Finder {
     }
 
     #[cfg(target_os = "wasi")]
-    fn path_split<U>(p: U) -> Vec<PathBuf>
+    pub(crate) fn path_split<U>(p: U) -> Vec<PathBuf>
     where
         U: AsRef<OsStr>,
     {
@@ -64,141 +141,468 @@ impl Finder {
     }
 
     #[cfg(not(target_os = "wasi"))]
-    fn path_split<U>(p: U) -> Vec<PathBuf>
+    pub(crate) fn path_split<U>(p: U) -> Vec<PathBuf>
     where
         U: AsRef<OsStr>,
     {
         std::env::split_paths(&p).collect()
     }
 
-    pub fn find<T, U, V>(
-        &self,
-        binary_name: T,
+    /// Builds the candidate list for `binary_name` along with the directories it covers.
+    #[allow(clippy::type_complexity)]
+    fn candidates<U, V>(
+        binary_name: &OsStr,
         paths: Option<U>,
         cwd: Option<V>,
-        binary_checker: CompositeChecker,
-    ) -> impl Stream<Item = Result<PathBuf>>
+    ) -> Result<(
+        Either<impl Iterator<Item = Candidate>, impl Iterator<Item = Candidate>>,
+        Vec<PathBuf>,
+    )>
     where
-        T: AsRef<OsStr>,
-        U: AsRef<OsStr>,
+        U: PathList,
         V: AsRef<Path>,
     {
-        let path = PathBuf::from(&binary_name);
+        if binary_name.is_empty() || binary_name.to_string_lossy().contains('\0') {
+            return Err(Error::InvalidName {
+                name: binary_name.to_owned(),
+            });
+        }
 
-        let binary_path_candidates = match (cwd, paths) {
+        let path = PathBuf::from(binary_name);
+
+        match (cwd, paths) {
             (Some(cwd), _) if path.has_separator() => {
                 // Search binary in cwd if the path have a path separator.
-                Ok(Either::Left(
-                    Self::cwd_search_candidates(path, cwd).into_iter(),
+                let search_paths = vec![cwd.as_ref().to_path_buf()];
+                Ok((
+                    Either::Left(Self::cwd_search_candidates(path, cwd)),
+                    search_paths,
                 ))
             }
             (_, Some(p)) => {
                 // Search binary in PATHs(defined in environment variable).
-                let paths = Self::path_split(p);
-                Ok(Either::Right(
-                    Self::path_search_candidates(path, paths).into_iter(),
+                let paths = p.into_dirs();
+                let search_paths = paths.clone();
+                Ok((
+                    Either::Right(Self::path_search_candidates(path, paths)),
+                    search_paths,
                 ))
             }
-            _ => Err(Error::CannotFindBinaryPath),
-        };
+            _ => Err(Error::PathUnset {
+                name: binary_name.to_owned(),
+            }),
+        }
+    }
+
+    pub fn find<T, U, V>(
+        &self,
+        binary_name: T,
+        paths: Option<U>,
+        cwd: Option<V>,
+        binary_checker: CompositeChecker,
+    ) -> impl Stream<Item = Result<PathBuf>>
+    where
+        T: AsRef<OsStr>,
+        U: PathList,
+        V: AsRef<Path>,
+    {
+        self.find_located(binary_name, paths, cwd, binary_checker)
+            .map_ok(|located| located.path)
+    }
+
+    /// Like [`Self::find`], but also yields the index of the path list entry each result was
+    /// found in.
+    pub fn find_located<T, U, V>(
+        &self,
+        binary_name: T,
+        paths: Option<U>,
+        cwd: Option<V>,
+        binary_checker: CompositeChecker,
+    ) -> impl Stream<Item = Result<Located>>
+    where
+        T: AsRef<OsStr>,
+        U: PathList,
+        V: AsRef<Path>,
+    {
+        let name = binary_name.as_ref().to_owned();
+        let binary_path_candidates = Self::candidates(&name, paths, cwd);
+
+        instrumented_stream!(
+            async_stream::try_stream! {
+                let (candidates, _) = binary_path_candidates?;
+                trace_event!(DEBUG, name = ?name, "searching for binary");
+                for (path_index, p) in candidates {
+                    let valid = instrumented!(
+                        binary_checker.is_valid(&p),
+                        "candidate",
+                        name = ?name,
+                        path = %p.display()
+                    )
+                    .await;
+                    if valid {
+                        yield Located {
+                            path: correct_casing(p).await,
+                            path_index,
+                        };
+                    }
+                }
+            },
+            "find_located",
+            name = ?name,
+            search_dirs = binary_path_candidates
+                .as_ref()
+                .map_or(0, |(_, dirs)| dirs.len())
+        )
+    }
+
+    /// Like [`Self::find`], but also yields candidates that exist and were rejected.
+    pub fn find_with_near_misses<T, U, V>(
+        &self,
+        binary_name: T,
+        paths: Option<U>,
+        cwd: Option<V>,
+        binary_checker: CompositeChecker,
+    ) -> impl Stream<Item = Result<Lookup>>
+    where
+        T: AsRef<OsStr>,
+        U: PathList,
+        V: AsRef<Path>,
+    {
+        let name = binary_name.as_ref().to_owned();
+        let binary_path_candidates = Self::candidates(&name, paths, cwd);
+
+        instrumented_stream!(
+            async_stream::try_stream! {
+                let (candidates, _) = binary_path_candidates?;
+                trace_event!(DEBUG, name = ?name, "searching for binary and near misses");
+                for (_, p) in candidates {
+                    let verdict = instrumented!(
+                        binary_checker.check(&p),
+                        "candidate",
+                        name = ?name,
+                        path = %p.display()
+                    )
+                    .await;
+                    match verdict {
+                        Verdict::Accepted => yield Lookup::Found(correct_casing(p).await),
+                        Verdict::Rejected(Rejection::Missing) => {}
+                        Verdict::Rejected(reason) => {
+                            if exists(&p).await {
+                                yield Lookup::NearMiss { path: p, reason };
+                            }
+                        }
+                    }
+                }
+            },
+            "find_with_near_misses",
+            name = ?name,
+            search_dirs = binary_path_candidates
+                .as_ref()
+                .map_or(0, |(_, dirs)| dirs.len())
+        )
+    }
 
-        async_stream::try_stream! {
-            for p in binary_path_candidates? {
-                println!("antes {:?}", p);
+    /// Returns the first valid candidate, or an error describing why none was accepted.
+    pub async fn find_first<T, U, V>(
+        &self,
+        binary_name: T,
+        paths: Option<U>,
+        cwd: Option<V>,
+        binary_checker: CompositeChecker,
+    ) -> Result<PathBuf>
+    where
+        T: AsRef<OsStr>,
+        U: PathList,
+        V: AsRef<Path>,
+    {
+        self.find_first_located(binary_name, paths, cwd, binary_checker)
+            .await
+            .map(|located| located.path)
+    }
 
-                if binary_checker.is_valid(&p).await {
-                println!("depois {:?}", p);
+    /// Like [`Self::find_first`], but also returns the index of the path list entry the result
+    /// was found in.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(name = ?binary_name.as_ref()))
+    )]
+    pub async fn find_first_located<T, U, V>(
+        &self,
+        binary_name: T,
+        paths: Option<U>,
+        cwd: Option<V>,
+        binary_checker: CompositeChecker,
+    ) -> Result<Located>
+    where
+        T: AsRef<OsStr>,
+        U: PathList,
+        V: AsRef<Path>,
+    {
+        let name = binary_name.as_ref();
+        let (candidates, search_paths) = Self::candidates(name, paths, cwd)?;
+
+        let mut near_miss = None;
+        for (path_index, p) in candidates {
+            let verdict = instrumented!(
+                binary_checker.check(&p),
+                "candidate",
+                path = %p.display()
+            )
+            .await;
+            match verdict {
+                Verdict::Accepted => {
+                    return Ok(Located {
+                        path: correct_casing(p).await,
+                        path_index,
+                    })
+                }
+                Verdict::Rejected(Rejection::Missing) => {}
+                Verdict::Rejected(reason) => {
+                    if near_miss.is_none() && exists(&p).await {
+                        near_miss = Some((p, reason));
+                    }
+                }
+            }
+        }
 
-                    yield correct_casing(p).await;
+        Err(not_found(name, search_paths, near_miss))
+    }
+
+    /// Returns the first valid candidate along with every later valid candidate it shadows.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(name = ?binary_name.as_ref()))
+    )]
+    pub async fn find_shadowed<T, U, V>(
+        &self,
+        binary_name: T,
+        paths: Option<U>,
+        cwd: Option<V>,
+        binary_checker: CompositeChecker,
+    ) -> Result<Shadowing>
+    where
+        T: AsRef<OsStr>,
+        U: PathList,
+        V: AsRef<Path>,
+    {
+        let name = binary_name.as_ref();
+        let (candidates, search_paths) = Self::candidates(name, paths, cwd)?;
+
+        let mut winner: Option<Located> = None;
+        let mut shadowed = Vec::new();
+        let mut near_miss = None;
+        for (path_index, p) in candidates {
+            let verdict = instrumented!(
+                binary_checker.check(&p),
+                "candidate",
+                path = %p.display()
+            )
+            .await;
+            match verdict {
+                Verdict::Accepted => {
+                    let path = correct_casing(p).await;
+                    match &winner {
+                        None => winner = Some(Located { path, path_index }),
+                        Some(winner) => shadowed.push(Shadowed {
+                            same_file: same_file(&winner.path, &path).await,
+                            path,
+                            path_index,
+                        }),
+                    }
+                }
+                Verdict::Rejected(Rejection::Missing) => {}
+                Verdict::Rejected(reason) => {
+                    if near_miss.is_none() && exists(&p).await {
+                        near_miss = Some((p, reason));
+                    }
                 }
             }
         }
+
+        match winner {
+            Some(winner) => Ok(Shadowing {
+                name: name.to_owned(),
+                search_paths,
+                winner,
+                shadowed,
+            }),
+            None => Err(not_found(name, search_paths, near_miss)),
+        }
     }
 
-    #[cfg(all(feature = "regex", all(target_os = "wasi")))]
-    fn select_all_files(paths: Vec<PathBuf>) -> impl Stream<Item = PathBuf> {
-        let iter = paths
-            .into_iter()
-            .map(std::fs::read_dir)
-            .filter_map(|f| f.ok())
-            .flatten()
-            .filter_map(|f| f.ok())
-            .map(|p| p.path());
+    /// Probes every candidate for `binary_name`, recording the outcome of each checker.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(name = ?binary_name.as_ref()))
+    )]
+    pub async fn explain<T, U, V>(
+        &self,
+        binary_name: T,
+        paths: Option<U>,
+        cwd: Option<V>,
+        binary_checker: CompositeChecker,
+    ) -> Result<SearchReport>
+    where
+        T: AsRef<OsStr>,
+        U: PathList,
+        V: AsRef<Path>,
+    {
+        let name = binary_name.as_ref();
+        let (candidates, search_paths) = Self::candidates(name, paths, cwd)?;
+
+        let mut probes = Vec::new();
+        for (path_index, candidate) in candidates {
+            let checks = binary_checker.explain(&candidate).await;
+            probes.push(Probe {
+                candidate,
+                path_index,
+                checks,
+            });
+        }
+
+        Ok(SearchReport {
+            name: name.to_owned(),
+            search_paths,
+            probes,
+        })
+    }
+
+    #[cfg(all(feature = "regex", target_os = "wasi"))]
+    fn select_all_files(paths: Vec<PathBuf>) -> impl Stream<Item = Result<PathBuf>> {
+        let iter = paths.into_iter().flat_map(|dir| {
+            trace_event!(TRACE, dir = %dir.display(), "reading directory");
+            let entries: Box<dyn Iterator<Item = Result<PathBuf>>> = match std::fs::read_dir(&dir) {
+                Ok(entries) => Box::new(entries.map(move |entry| {
+                    entry
+                        .map(|e| e.path())
+                        .map_err(|source| unreadable_directory(&dir, source))
+                })),
+                Err(source) => Box::new(iter::once(Err(unreadable_directory(&dir, source)))),
+            };
+            entries
+        });
 
         stream::iter(iter)
     }
 
     #[cfg(all(feature = "regex", not(target_os = "wasi")))]
-    fn select_all_files(paths: Vec<PathBuf>) -> impl Stream<Item = PathBuf> {
-        use futures::stream::FuturesUnordered;
+    fn select_all_files(paths: Vec<PathBuf>) -> impl Stream<Item = Result<PathBuf>> {
+        use futures::stream::FuturesOrdered;
         use tokio_stream::wrappers::ReadDirStream;
 
         let jobs = paths
             .into_iter()
-            .map(|f| tokio::fs::read_dir(f))
-            .collect::<FuturesUnordered<_>>();
+            .map(|dir| async move {
+                trace_event!(TRACE, dir = %dir.display(), "reading directory");
+                match tokio::fs::read_dir(&dir).await {
+                    Ok(entries) => ReadDirStream::new(entries)
+                        .map(move |entry| {
+                            entry
+                                .map(|e| e.path())
+                                .map_err(|source| unreadable_directory(&dir, source))
+                        })
+                        .left_stream(),
+                    Err(source) => {
+                        stream::once(future::ready(Err(unreadable_directory(&dir, source))))
+                            .right_stream()
+                    }
+                }
+            })
+            .collect::<FuturesOrdered<_>>();
 
-        jobs.map_ok(ReadDirStream::new)
-            .try_flatten()
-            .filter_map(|f| async { f.ok() })
-            .map(|f| f.path())
+        jobs.flatten()
     }
 
+    /// Finds every file matching `binary_regex` in `paths`.
+    ///
+    /// With `report_unreadable`, directories (or entries) that cannot be read are yielded as
+    /// [`Error::UnreadableDirectory`] items and the search carries on; otherwise they are skipped.
     #[cfg(feature = "regex")]
-    pub fn find_re<T>(
+    pub fn find_re<M, T>(
         &self,
-        binary_regex: impl Borrow<Regex>,
+        binary_regex: M,
         paths: T,
         binary_checker: CompositeChecker,
+        report_unreadable: bool,
     ) -> impl Stream<Item = Result<PathBuf>>
     where
-        T: AsRef<OsStr>,
+        M: FileNameMatcher,
+        T: PathList,
     {
-        let paths = Self::path_split(paths);
-        async_stream::try_stream! {
-            for await f in Self::select_all_files(paths) {
-                if let Some(unicode_file_name) =  f.file_name().and_then(OsStr::to_str) {
-                    if binary_regex.borrow().is_match(&unicode_file_name) && binary_checker.is_valid(&f).await {
-                        yield f;
+        let paths = paths.into_dirs();
+        instrumented_stream!(
+            async_stream::stream! {
+                trace_event!(DEBUG, regex = binary_regex.as_str(), "searching for binaries");
+                for await f in Self::select_all_files(paths) {
+                    let f = match f {
+                        Ok(f) => f,
+                        Err(error) => {
+                            trace_event!(DEBUG, error = %error, "unreadable directory");
+                            if report_unreadable {
+                                yield Err(error);
+                            }
+                            continue;
+                        }
+                    };
+                    if let Some(file_name) = f.file_name() {
+                        if binary_regex.is_match_file_name(file_name)
+                            && instrumented!(
+                                binary_checker.is_valid(&f),
+                                "candidate",
+                                path = %f.display()
+                            )
+                            .await
+                        {
+                            yield Ok(f);
+                        }
                     }
                 }
-            }
-        }
+            },
+            "find_re",
+            regex = binary_regex.as_str(),
+            search_dirs = paths.len()
+        )
     }
 
-    fn cwd_search_candidates<C>(binary_name: PathBuf, cwd: C) -> impl IntoIterator<Item = PathBuf>
+    fn cwd_search_candidates<C>(
+        binary_name: PathBuf,
+        cwd: C,
+    ) -> impl Iterator<Item = Candidate> + Send
     where
         C: AsRef<Path>,
     {
         let path = binary_name.to_absolute(cwd);
 
-        Self::append_extension(iter::once(path))
+        Self::append_extension(iter::once(path)).map(|p| (None, p))
     }
 
     fn path_search_candidates<P>(
         binary_name: PathBuf,
         paths: P,
-    ) -> impl IntoIterator<Item = PathBuf>
+    ) -> impl Iterator<Item = Candidate> + Send
     where
         P: IntoIterator<Item = PathBuf>,
+        P::IntoIter: Send,
     {
-        let new_paths = paths.into_iter().map(move |p| p.join(binary_name.clone()));
-
-        Self::append_extension(new_paths)
+        paths.into_iter().enumerate().flat_map(move |(index, p)| {
+            Self::append_extension(iter::once(p.join(&binary_name))).map(move |p| (Some(index), p))
+        })
     }
 
     #[cfg(unix)]
-    fn append_extension<P>(paths: P) -> impl IntoIterator<Item = PathBuf>
+    fn append_extension<P>(paths: P) -> impl Iterator<Item = PathBuf> + Send
     where
         P: IntoIterator<Item = PathBuf>,
+        P::IntoIter: Send,
     {
-        paths
+        paths.into_iter()
     }
 
     #[cfg(windows)]
-    fn append_extension<P>(paths: P) -> impl IntoIterator<Item = PathBuf>
+    fn append_extension<P>(paths: P) -> impl Iterator<Item = PathBuf> + Send
     where
         P: IntoIterator<Item = PathBuf>,
+        P::IntoIter: Send,
     {
         use once_cell::sync::Lazy;
 
@@ -228,7 +632,7 @@ impl Finder {
 
         paths
             .into_iter()
-            .flat_map(move |p| -> Box<dyn Iterator<Item = _>> {
+            .flat_map(move |p| -> Box<dyn Iterator<Item = _> + Send> {
                 // Check if path already have executable extension
                 if has_executable_extension(&p, &PATH_EXTENSIONS) {
                     Box::new(iter::once(p))
@@ -257,9 +661,10 @@ impl Finder {
     }
 
     #[cfg(target_os = "wasi")]
-    fn append_extension<P>(paths: P) -> impl IntoIterator<Item = PathBuf>
+    fn append_extension<P>(paths: P) -> impl Iterator<Item = PathBuf> + Send
     where
         P: IntoIterator<Item = PathBuf>,
+        P::IntoIter: Send,
     {
         paths
             .into_iter()
@@ -268,6 +673,7 @@ impl Finder {
 }
 
 #[cfg(target_os = "windows")]
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", ret))]
 async fn correct_casing(mut p: PathBuf) -> PathBuf {
     if let (Some(parent), Some(file_name)) = (p.parent(), p.file_name()) {
         if let Ok(mut iter) = tokio::fs::read_dir(parent).await {
@@ -286,6 +692,48 @@ async fn correct_casing(mut p: PathBuf) -> PathBuf {
 }
 
 #[cfg(not(target_os = "windows"))]
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", ret))]
 async fn correct_casing(p: PathBuf) -> PathBuf {
     p
 }
+
+/// Whether something exists at `path`, so that its rejection is worth reporting. Custom checkers
+/// may reject candidates that are not there without saying [`Rejection::Missing`].
+async fn exists(path: &Path) -> bool {
+    tokio::fs::symlink_metadata(path).await.is_ok()
+}
+
+#[cfg(feature = "regex")]
+fn unreadable_directory(dir: &Path, source: io::Error) -> Error {
+    Error::UnreadableDirectory {
+        path: dir.to_path_buf(),
+        source,
+    }
+}
+
+/// The error for a lookup that accepted no candidate, preferring the first near miss.
+fn not_found(
+    name: &OsStr,
+    search_paths: Vec<PathBuf>,
+    near_miss: Option<(PathBuf, Rejection)>,
+) -> Error {
+    match near_miss {
+        Some((candidate, reason @ Rejection::NotExecutable { mode, .. })) => Error::NotExecutable {
+            name: name.to_owned(),
+            candidate,
+            mode,
+            source: reason
+                .io_error()
+                .unwrap_or_else(|| io::ErrorKind::PermissionDenied.into()),
+        },
+        Some((candidate, reason)) => Error::Rejected {
+            name: name.to_owned(),
+            candidate,
+            reason,
+        },
+        None => Error::CannotFindBinaryPath {
+            name: name.to_owned(),
+            search_paths,
+        },
+    }
+}
@@ -0,0 +1,238 @@
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+use tokio::io::AsyncReadExt;
+
+/// How many leading bytes [`BinaryKind::from_header`] looks at.
+const HEADER_LEN: usize = 8;
+
+/// The format of an executable file, as told by its first bytes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum BinaryKind {
+    /// An ELF executable, as used on Linux and most other Unix systems.
+    Elf,
+    /// A PE (`MZ`) executable, as used on Windows.
+    Pe,
+    /// A Mach-O executable or universal binary, as used on macOS.
+    MachO,
+    /// A WebAssembly module.
+    Wasm,
+    /// A script starting with a `#!` line.
+    Script,
+    /// A zero-byte file.
+    Empty,
+    /// Anything else, e.g. a truncated download or a script without a `#!` line.
+    Unknown,
+}
+
+impl BinaryKind {
+    /// Classifies a file from its first bytes. Pass at least the first 8 bytes, or the whole
+    /// file if it is shorter.
+    pub fn from_header(header: &[u8]) -> BinaryKind {
+        match header {
+            [] => BinaryKind::Empty,
+            [0x7f, b'E', b'L', b'F', ..] => BinaryKind::Elf,
+            [b'M', b'Z', ..] => BinaryKind::Pe,
+            [0xfe, 0xed, 0xfa, 0xce | 0xcf, ..] | [0xce | 0xcf, 0xfa, 0xed, 0xfe, ..] => {
+                BinaryKind::MachO
+            }
+            // Java class files share this magic; universal binaries follow it with a small
+            // architecture count where class files have their version.
+            [0xca, 0xfe, 0xba, 0xbe, a, b, c, d, ..]
+                if u32::from_be_bytes([*a, *b, *c, *d]) < 45 =>
+            {
+                BinaryKind::MachO
+            }
+            [0x00, b'a', b's', b'm', ..] => BinaryKind::Wasm,
+            [b'#', b'!', ..] => BinaryKind::Script,
+            _ => BinaryKind::Unknown,
+        }
+    }
+
+    /// Whether this is a compiled binary rather than a script, empty or unrecognized file.
+    pub fn is_binary(&self) -> bool {
+        matches!(
+            self,
+            BinaryKind::Elf | BinaryKind::Pe | BinaryKind::MachO | BinaryKind::Wasm
+        )
+    }
+}
+
+impl fmt::Display for BinaryKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BinaryKind::Elf => write!(f, "ELF binary"),
+            BinaryKind::Pe => write!(f, "PE binary"),
+            BinaryKind::MachO => write!(f, "Mach-O binary"),
+            BinaryKind::Wasm => write!(f, "WebAssembly module"),
+            BinaryKind::Script => write!(f, "script"),
+            BinaryKind::Empty => write!(f, "empty file"),
+            BinaryKind::Unknown => write!(f, "unrecognized file"),
+        }
+    }
+}
+
+/// Reads the start of the file at `path` and classifies it.
+///
+/// # Example
+///
+/// ```no_run
+/// # tokio_test::block_on(async {
+/// use async_which::BinaryKind;
+///
+/// let rustc = async_which::which("rustc").await.unwrap();
+/// assert_eq!(async_which::binary_kind(&rustc).await.unwrap(), BinaryKind::Elf);
+/// # })
+/// ```
+pub async fn binary_kind<P: AsRef<Path>>(path: P) -> io::Result<BinaryKind> {
+    let mut header = [0; HEADER_LEN];
+    let len = read_header(path.as_ref(), &mut header).await?;
+    Ok(BinaryKind::from_header(&header[..len]))
+}
+
+/// The machine an ELF binary was built for, from its `EI_CLASS`, `EI_DATA` and `e_machine`
+/// header fields.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ElfArch {
+    /// The `e_machine` field, e.g. 62 for x86-64 or 183 for AArch64.
+    pub machine: u16,
+    /// Whether this is a 64-bit (`ELFCLASS64`) rather than a 32-bit binary.
+    pub is_64_bit: bool,
+    /// Whether this is a little-endian (`ELFDATA2LSB`) rather than a big-endian binary.
+    pub is_little_endian: bool,
+}
+
+/// The size of the ELF header up to and including `e_machine`.
+pub(crate) const ELF_HEADER_LEN: usize = 20;
+
+impl ElfArch {
+    /// Reads the architecture from the start of an ELF file, or returns `None` if `header` is
+    /// not a well-formed ELF header.
+    pub fn from_header(header: &[u8]) -> Option<ElfArch> {
+        if header.len() < ELF_HEADER_LEN || BinaryKind::from_header(header) != BinaryKind::Elf {
+            return None;
+        }
+        let is_64_bit = match header[4] {
+            1 => false,
+            2 => true,
+            _ => return None,
+        };
+        let (is_little_endian, machine) = match header[5] {
+            1 => (true, u16::from_le_bytes([header[18], header[19]])),
+            2 => (false, u16::from_be_bytes([header[18], header[19]])),
+            _ => return None,
+        };
+        Some(ElfArch {
+            machine,
+            is_64_bit,
+            is_little_endian,
+        })
+    }
+
+    /// The architecture of binaries built for the running platform, or `None` if it does not
+    /// use ELF or is not known to this crate.
+    pub fn host() -> Option<ElfArch> {
+        let mut arch = ElfArch::from_arch_name(std::env::consts::ARCH)?;
+        arch.is_64_bit = cfg!(target_pointer_width = "64");
+        arch.is_little_endian = cfg!(target_endian = "little");
+        Some(arch)
+    }
+
+    /// The architecture of binaries built for a target triple such as
+    /// `aarch64-unknown-linux-gnu`, or `None` if its architecture is not known to this crate.
+    pub fn for_target(triple: &str) -> Option<ElfArch> {
+        let mut arch = ElfArch::from_arch_name(triple.split('-').next()?)?;
+        if triple.ends_with("x32") {
+            arch.is_64_bit = false;
+        }
+        Some(arch)
+    }
+
+    fn from_arch_name(name: &str) -> Option<ElfArch> {
+        let (machine, is_64_bit, is_little_endian) = match name {
+            "x86_64" | "amd64" => (62, true, true),
+            "x86" | "i386" | "i486" | "i586" | "i686" => (3, false, true),
+            "aarch64" | "arm64" => (183, true, true),
+            "aarch64_be" => (183, true, false),
+            "armeb" | "armebv7r" => (40, false, false),
+            "powerpc" => (20, false, false),
+            "powerpc64" => (21, true, false),
+            "powerpc64le" => (21, true, true),
+            "mips" => (8, false, false),
+            "mipsel" => (8, false, true),
+            "mips64" => (8, true, false),
+            "mips64el" => (8, true, true),
+            "s390x" => (22, true, false),
+            "sparc64" => (43, true, false),
+            "loongarch64" => (258, true, true),
+            _ if name.starts_with("arm") || name.starts_with("thumb") => (40, false, true),
+            _ if name.starts_with("riscv32") => (243, false, true),
+            _ if name.starts_with("riscv64") => (243, true, true),
+            _ => return None,
+        };
+        Some(ElfArch {
+            machine,
+            is_64_bit,
+            is_little_endian,
+        })
+    }
+
+    fn machine_name(&self) -> Option<&'static str> {
+        Some(match self.machine {
+            3 => "x86",
+            8 => "mips",
+            20 => "powerpc",
+            21 => "powerpc64",
+            22 => "s390",
+            40 => "arm",
+            43 => "sparcv9",
+            62 => "x86_64",
+            183 => "aarch64",
+            243 => "riscv",
+            258 => "loongarch",
+            _ => return None,
+        })
+    }
+}
+
+impl fmt::Display for ElfArch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.machine_name() {
+            Some(name) => write!(f, "{}", name)?,
+            None => write!(f, "machine {}", self.machine)?,
+        }
+        write!(
+            f,
+            " ({}-bit {}-endian)",
+            if self.is_64_bit { 64 } else { 32 },
+            if self.is_little_endian {
+                "little"
+            } else {
+                "big"
+            }
+        )
+    }
+}
+
+/// Reads the architecture of the ELF file at `path`, or returns `None` if it is not an ELF
+/// file.
+pub async fn elf_arch<P: AsRef<Path>>(path: P) -> io::Result<Option<ElfArch>> {
+    let mut header = [0; ELF_HEADER_LEN];
+    let len = read_header(path.as_ref(), &mut header).await?;
+    Ok(ElfArch::from_header(&header[..len]))
+}
+
+/// Fills `header` from the start of the file at `path`, returning how many bytes were read.
+pub(crate) async fn read_header(path: &Path, header: &mut [u8]) -> io::Result<usize> {
+    let mut file = tokio::fs::File::open(path).await?;
+    let mut len = 0;
+    while len < header.len() {
+        match file.read(&mut header[len..]).await? {
+            0 => break,
+            n => len += n,
+        }
+    }
+    Ok(len)
+}
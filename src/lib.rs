@@ -17,29 +17,78 @@
 //! # })
 //! ```
 
-mod checker;
+#[macro_use]
+mod trace;
+
+mod audit;
+pub mod checker;
+#[cfg(feature = "checksum")]
+mod checksum;
 mod error;
 mod finder;
+mod format;
 #[cfg(windows)]
 mod helper;
+mod lookup;
+mod project;
+mod python;
+mod report;
+#[cfg(feature = "rustup")]
+mod rustup;
+mod search_path;
+mod shadow;
+mod shebang;
+mod symlink;
+mod sysroot;
+mod verdict;
+#[cfg(feature = "version")]
+mod version;
 
 #[cfg(feature = "regex")]
 use std::borrow::Borrow;
 use std::env;
 use std::fmt;
+#[cfg(feature = "regex")]
 use std::future;
+use std::io;
 use std::path;
 
 use std::ffi::{OsStr, OsString};
+#[cfg(feature = "regex")]
 use std::pin::pin;
 
 use futures::stream;
 use futures::Stream;
 use futures::StreamExt;
-
-use crate::checker::{CompositeChecker, ExecutableChecker, ExistedChecker};
+use futures::TryStreamExt;
+
+pub use crate::audit::*;
+use crate::checker::{
+    AccessMode, Checker, CompositeChecker, Evaluation, ExecutableChecker, ExistedChecker,
+    SecureChecker, SymlinkChecker, SymlinkPolicy,
+};
+#[cfg(feature = "checksum")]
+pub use crate::checksum::{sha256_file, DigestParseError, Sha256Digest};
 pub use crate::error::*;
 use crate::finder::Finder;
+#[cfg(feature = "regex")]
+use crate::finder::{FileNameMatcher, NamePattern};
+pub use crate::format::*;
+pub use crate::lookup::*;
+pub use crate::project::{ProjectBins, ProjectBoundary};
+pub use crate::python::{Python, PythonEnv, PythonEnvKind, PythonEnvSource, PythonTool};
+pub use crate::report::*;
+#[cfg(feature = "rustup")]
+pub use crate::rustup::{which_rustup, Rustup, RustupTool, Toolchain, ToolchainSource};
+use crate::search_path::system_search_path;
+pub use crate::search_path::{SearchEntry, SearchPath, SourcedPath};
+pub use crate::shadow::*;
+pub use crate::shebang::*;
+pub use crate::symlink::{symlink_chain, SymlinkChain, SymlinkHop};
+pub use crate::sysroot::Sysroot;
+pub use crate::verdict::*;
+#[cfg(feature = "version")]
+pub use crate::version::{Version, VersionParseError, VersionReq};
 
 /// Find an executable binary's path by name.
 ///
@@ -66,11 +115,35 @@ use crate::finder::Finder;
 /// # })
 /// ```
 pub async fn which<T: AsRef<OsStr>>(binary_name: T) -> Result<path::PathBuf> {
-    let candidates = which_all(binary_name).take_while(|x| future::ready(x.is_ok()));
-    pin!(candidates)
-        .next()
+    let cwd = env::current_dir().ok();
+
+    let binary_checker = build_binary_checker();
+
+    let finder = Finder::new();
+
+    finder
+        .find_first(binary_name, env::var_os("PATH"), cwd, binary_checker)
+        .await
+}
+
+/// Find an executable binary's path by name, additionally requiring `checker` to accept it.
+///
+/// Behaves like [`which`], with `checker` running after the default existence and
+/// executable checks.
+pub async fn which_with_checker<T, C>(binary_name: T, checker: C) -> Result<path::PathBuf>
+where
+    T: AsRef<OsStr>,
+    C: Checker + 'static,
+{
+    let cwd = env::current_dir().ok();
+
+    let binary_checker = build_binary_checker().add_checker(Box::new(checker));
+
+    let finder = Finder::new();
+
+    finder
+        .find_first(binary_name, env::var_os("PATH"), cwd, binary_checker)
         .await
-        .unwrap_or(Err(Error::CannotFindBinaryPath))
 }
 
 /// Find an executable binary's path by name, ignoring `cwd`.
@@ -95,11 +168,92 @@ pub async fn which<T: AsRef<OsStr>>(binary_name: T) -> Result<path::PathBuf> {
 /// # })
 /// ```
 pub async fn which_global<T: AsRef<OsStr>>(binary_name: T) -> Result<path::PathBuf> {
-    let candidates = which_all_global(binary_name).take_while(|x| future::ready(x.is_ok()));
-    pin!(candidates)
-        .next()
+    let binary_checker = build_binary_checker();
+
+    let finder = Finder::new();
+
+    finder
+        .find_first(
+            binary_name,
+            env::var_os("PATH"),
+            Option::<&Path>::None,
+            binary_checker,
+        )
+        .await
+}
+
+/// Probe every candidate for `binary_name`, recording why each was accepted or rejected.
+///
+/// Uses `cwd` to resolve relative paths, like [`which`]. The report's `Display`
+/// implementation is suitable for logging.
+///
+/// # Example
+///
+/// ```no_run
+/// # tokio_test::block_on(async {
+/// let report = async_which::which_explain("rustc").await.unwrap();
+/// println!("{}", report);
+/// # })
+/// ```
+pub async fn which_explain<T: AsRef<OsStr>>(binary_name: T) -> Result<SearchReport> {
+    let cwd = env::current_dir().ok();
+
+    let binary_checker = build_binary_checker();
+
+    let finder = Finder::new();
+
+    finder
+        .explain(binary_name, env::var_os("PATH"), cwd, binary_checker)
         .await
-        .unwrap_or(Err(Error::CannotFindBinaryPath))
+}
+
+/// Find the binary `which` would return for `binary_name`, plus every other binary of the same
+/// name further along `PATH` that it shadows.
+///
+/// Uses `cwd` to resolve relative paths, like [`which`].
+///
+/// # Example
+///
+/// ```no_run
+/// # tokio_test::block_on(async {
+/// let shadowing = async_which::which_shadowed("python3").await.unwrap();
+/// for shadowed in shadowing.distinct() {
+///     println!("{} is hidden by {}", shadowed.path.display(), shadowing.winner.path.display());
+/// }
+/// # })
+/// ```
+pub async fn which_shadowed<T: AsRef<OsStr>>(binary_name: T) -> Result<Shadowing> {
+    let cwd = env::current_dir().ok();
+
+    let binary_checker = build_binary_checker();
+
+    let finder = Finder::new();
+
+    finder
+        .find_shadowed(binary_name, env::var_os("PATH"), cwd, binary_checker)
+        .await
+}
+
+/// Find the first binary with `binary_name` whose reported version satisfies `req`, using `cwd`
+/// to resolve relative paths.
+///
+/// Every candidate up to the one returned is **run** with `--version`; see
+/// [`VersionChecker`](checker::VersionChecker) to change the argument, timeout or pattern.
+///
+/// # Example
+///
+/// ```no_run
+/// # tokio_test::block_on(async {
+/// let req = async_which::VersionReq::parse(">=3.20").unwrap();
+/// let cmake = async_which::which_version("cmake", req).await.unwrap();
+/// # })
+/// ```
+#[cfg(feature = "version")]
+pub async fn which_version<T: AsRef<OsStr>>(
+    binary_name: T,
+    req: VersionReq,
+) -> Result<path::PathBuf> {
+    which_with_checker(binary_name, checker::VersionChecker::new(req)).await
 }
 
 /// Find all binaries with `binary_name` using `cwd` to resolve relative paths.
@@ -113,6 +267,58 @@ pub fn which_all<T: AsRef<OsStr>>(binary_name: T) -> impl Stream<Item = Result<p
     finder.find(binary_name, env::var_os("PATH"), cwd, binary_checker)
 }
 
+/// Find all binaries with `binary_name` accepted by `checker`, using `cwd` to resolve relative paths.
+pub fn which_all_with_checker<T, C>(
+    binary_name: T,
+    checker: C,
+) -> impl Stream<Item = Result<path::PathBuf>>
+where
+    T: AsRef<OsStr>,
+    C: Checker + 'static,
+{
+    let cwd = env::current_dir().ok();
+
+    let binary_checker = build_binary_checker().add_checker(Box::new(checker));
+
+    let finder = Finder::new();
+
+    finder.find(binary_name, env::var_os("PATH"), cwd, binary_checker)
+}
+
+/// Find all binaries with `binary_name`, also reporting candidates that exist but were rejected.
+///
+/// Uses `cwd` to resolve relative paths, like [`which_all`].
+///
+/// # Example
+///
+/// ```no_run
+/// # tokio_test::block_on(async {
+/// use async_which::Lookup;
+/// use futures::TryStreamExt;
+///
+/// let lookups: Vec<Lookup> = async_which::which_all_with_near_misses("deploy")
+///     .try_collect()
+///     .await
+///     .unwrap();
+/// for lookup in lookups {
+///     if let Lookup::NearMiss { path, reason } = lookup {
+///         println!("found {} but it {}", path.display(), reason);
+///     }
+/// }
+/// # })
+/// ```
+pub fn which_all_with_near_misses<T: AsRef<OsStr>>(
+    binary_name: T,
+) -> impl Stream<Item = Result<Lookup>> {
+    let cwd = env::current_dir().ok();
+
+    let binary_checker = build_binary_checker();
+
+    let finder = Finder::new();
+
+    finder.find_with_near_misses(binary_name, env::var_os("PATH"), cwd, binary_checker)
+}
+
 /// Find all binaries with `binary_name` ignoring `cwd`.
 pub fn which_all_global<T: AsRef<OsStr>>(
     binary_name: T,
@@ -181,7 +387,8 @@ pub fn which_re(regex: impl Borrow<Regex>) -> impl Stream<Item = Result<path::Pa
         let regex = regex.borrow().clone();
         which_re_in(regex, path).boxed_local()
     } else {
-        stream::once(future::ready(Err(Error::CannotFindBinaryPath))).boxed()
+        let name = OsString::from(regex.borrow().as_str());
+        stream::once(future::ready(Err(Error::PathUnset { name }))).boxed()
     }
 }
 
@@ -192,12 +399,36 @@ where
     U: AsRef<OsStr>,
     V: AsRef<path::Path>,
 {
-    let mut candidates =
-        which_in_all(binary_name, paths, cwd).take_while(|x| future::ready(x.is_ok()));
-    pin!(candidates)
-        .next()
+    let binary_checker = build_binary_checker();
+
+    let finder = Finder::new();
+
+    finder
+        .find_first(binary_name, paths, Some(cwd), binary_checker)
+        .await
+}
+
+/// Find `binary_name` accepted by `checker` in the path list `paths`, using `cwd` to resolve
+/// relative paths.
+pub async fn which_in_with_checker<T, U, V, C>(
+    binary_name: T,
+    paths: Option<U>,
+    cwd: V,
+    checker: C,
+) -> Result<path::PathBuf>
+where
+    T: AsRef<OsStr>,
+    U: AsRef<OsStr>,
+    V: AsRef<path::Path>,
+    C: Checker + 'static,
+{
+    let binary_checker = build_binary_checker().add_checker(Box::new(checker));
+
+    let finder = Finder::new();
+
+    finder
+        .find_first(binary_name, paths, Some(cwd), binary_checker)
         .await
-        .unwrap_or(Err(Error::CannotFindBinaryPath))
 }
 
 /// Find all binaries matching a regular expression in a list of paths.
@@ -208,7 +439,7 @@ where
 ///
 /// * `regex` - A regular expression to match binaries with
 /// * `paths` - A string containing the paths to search
-///             (separated in the same way as the PATH environment variable)
+///   (separated in the same way as the PATH environment variable)
 ///
 /// # Examples
 ///
@@ -239,7 +470,96 @@ where
     let binary_checker = build_binary_checker();
 
     let finder = Finder::new();
-    finder.find_re(regex, paths, binary_checker)
+    finder.find_re(regex.borrow().clone(), paths, binary_checker, false)
+}
+
+/// Find all binaries whose raw file name matches a byte-oriented regular expression in the
+/// system PATH.
+///
+/// On Unix, file names are matched byte for byte, so executables whose names are not valid
+/// UTF-8 (e.g. Latin-1) are found too. Elsewhere the lossy UTF-8 form of the name is matched.
+///
+/// Only available when feature `regex` is enabled.
+///
+/// # Examples
+///
+/// ```no_run
+/// # tokio_test::block_on(async {
+/// use futures::TryStreamExt;
+/// use regex::bytes::Regex;
+///
+/// // Matches `caf\xe9`, "café" in Latin-1.
+/// let re = Regex::new(r"(?-u)^caf\xE9$").unwrap();
+/// let binaries: Vec<_> = async_which::which_re_bytes(re).try_collect().await.unwrap();
+/// # })
+/// ```
+#[cfg(feature = "regex")]
+pub fn which_re_bytes(
+    regex: impl Borrow<regex::bytes::Regex>,
+) -> impl Stream<Item = Result<path::PathBuf>> {
+    if let Some(path) = env::var_os("PATH") {
+        let regex = regex.borrow().clone();
+        which_re_bytes_in(regex, path).boxed_local()
+    } else {
+        let name = OsString::from(regex.borrow().as_str());
+        stream::once(future::ready(Err(Error::PathUnset { name }))).boxed()
+    }
+}
+
+/// Find all binaries whose raw file name matches a byte-oriented regular expression in a list
+/// of paths. See [`which_re_bytes`].
+///
+/// Only available when feature `regex` is enabled.
+#[cfg(feature = "regex")]
+pub fn which_re_bytes_in<T>(
+    regex: impl Borrow<regex::bytes::Regex>,
+    paths: T,
+) -> impl Stream<Item = Result<path::PathBuf>>
+where
+    T: AsRef<OsStr>,
+{
+    let binary_checker = build_binary_checker();
+
+    let finder = Finder::new();
+    finder.find_re(regex.borrow().clone(), paths, binary_checker, false)
+}
+
+/// Find all binaries matching a regular expression in a list of paths, yielding an
+/// [`Error::UnreadableDirectory`] item for every directory that cannot be read.
+///
+/// Unlike [`which_re_in`], which silently skips such directories, the stream carries on after
+/// each error so callers can collect them as warnings.
+///
+/// Only available when feature `regex` is enabled.
+///
+/// # Examples
+///
+/// ```no_run
+/// # tokio_test::block_on(async {
+/// use futures::StreamExt;
+/// use regex::Regex;
+///
+/// let re = Regex::new(r"^python\d$").unwrap();
+/// let (found, warnings): (Vec<_>, Vec<_>) =
+///     async_which::which_re_in_reporting_errors(re, "/usr/bin:/mnt/stale")
+///         .collect::<Vec<_>>()
+///         .await
+///         .into_iter()
+///         .partition(|r| r.is_ok());
+/// # })
+/// ```
+#[cfg(feature = "regex")]
+pub fn which_re_in_reporting_errors<T>(
+    regex: impl Borrow<Regex>,
+    paths: T,
+) -> impl Stream<Item = Result<path::PathBuf>>
+where
+    T: AsRef<OsStr>,
+{
+    let binary_checker = build_binary_checker();
+
+    let finder = Finder::new();
+    finder.find_re(regex.borrow().clone(), paths, binary_checker, true)
 }
 
 /// Find all binaries with `binary_name` in the path list `paths`, using `cwd` to resolve relative paths.
@@ -260,6 +580,46 @@ where
     finder.find(binary_name, paths, Some(cwd), binary_checker)
 }
 
+/// Find all binaries with `binary_name` accepted by `checker` in the path list `paths`, using
+/// `cwd` to resolve relative paths.
+pub fn which_in_all_with_checker<T, U, V, C>(
+    binary_name: T,
+    paths: Option<U>,
+    cwd: V,
+    checker: C,
+) -> impl Stream<Item = Result<path::PathBuf>>
+where
+    T: AsRef<OsStr>,
+    U: AsRef<OsStr>,
+    V: AsRef<path::Path>,
+    C: Checker + 'static,
+{
+    let binary_checker = build_binary_checker().add_checker(Box::new(checker));
+
+    let finder = Finder::new();
+
+    finder.find(binary_name, paths, Some(cwd), binary_checker)
+}
+
+/// Find all binaries with `binary_name` in the path list `paths`, also reporting candidates
+/// that exist but were rejected. Uses `cwd` to resolve relative paths.
+pub fn which_in_all_with_near_misses<T, U, V>(
+    binary_name: T,
+    paths: Option<U>,
+    cwd: V,
+) -> impl Stream<Item = Result<Lookup>>
+where
+    T: AsRef<OsStr>,
+    U: AsRef<OsStr>,
+    V: AsRef<path::Path>,
+{
+    let binary_checker = build_binary_checker();
+
+    let finder = Finder::new();
+
+    finder.find_with_near_misses(binary_name, paths, Some(cwd), binary_checker)
+}
+
 /// Find all binaries with `binary_name` in the path list `paths`, ignoring `cwd`.
 pub fn which_in_global<T, U>(
     binary_name: T,
@@ -276,6 +636,40 @@ where
     finder.find(binary_name, paths, Option::<&Path>::None, binary_checker)
 }
 
+fn canonicalize(path: path::PathBuf) -> Result<path::PathBuf> {
+    path.canonicalize()
+        .map_err(|source| Error::CannotCanonicalize { path, source })
+}
+
+/// The configured search path, or `PATH`, after the project-local bin directories above `cwd`.
+async fn resolve_search_path(
+    search_path: Option<SearchPath>,
+    project_bins: Option<ProjectBins>,
+    cwd: Option<path::PathBuf>,
+) -> Option<SearchPath> {
+    let search_path = search_path.or_else(system_search_path);
+    let project = match (project_bins, cwd) {
+        (Some(project_bins), Some(cwd)) => project_bins.search_path(cwd).await,
+        _ => return search_path,
+    };
+    match search_path {
+        Some(search_path) => Some(project.append(search_path)),
+        None if project.is_empty() => None,
+        None => Some(project),
+    }
+}
+
+fn sourced(located: Located, entries: Option<&[SearchEntry]>) -> SourcedPath {
+    let entry = located
+        .path_index
+        .and_then(|index| entries?.get(index))
+        .cloned();
+    SourcedPath {
+        path: located.path,
+        entry,
+    }
+}
+
 fn build_binary_checker() -> CompositeChecker {
     CompositeChecker::new()
         .add_checker(Box::new(ExistedChecker::new()))
@@ -285,20 +679,38 @@ fn build_binary_checker() -> CompositeChecker {
 /// A wrapper containing all functionality in this crate.
 pub struct WhichConfig {
     cwd: Option<either::Either<bool, path::PathBuf>>,
-    custom_path_list: Option<OsString>,
+    search_path: Option<SearchPath>,
+    project_bins: Option<ProjectBins>,
     binary_name: Option<OsString>,
     #[cfg(feature = "regex")]
-    regex: Option<Regex>,
+    regex: Option<NamePattern>,
+    checkers: Vec<Box<dyn Checker>>,
+    evaluation: Evaluation,
+    report_unreadable_dirs: bool,
+    secure: bool,
+    access_mode: AccessMode,
+    symlink_policy: Option<SymlinkPolicy>,
+    #[cfg(feature = "version")]
+    version_req: Option<VersionReq>,
 }
 
 impl Default for WhichConfig {
     fn default() -> Self {
         Self {
             cwd: Some(either::Either::Left(true)),
-            custom_path_list: None,
+            search_path: None,
+            project_bins: None,
             binary_name: None,
             #[cfg(feature = "regex")]
             regex: None,
+            checkers: Vec::new(),
+            evaluation: Evaluation::default(),
+            report_unreadable_dirs: false,
+            secure: false,
+            access_mode: AccessMode::default(),
+            symlink_policy: None,
+            #[cfg(feature = "version")]
+            version_req: None,
         }
     }
 }
@@ -367,11 +779,32 @@ impl WhichConfig {
             if self.binary_name.is_some() {
                 panic!("which can't use `binary_name` and `regex` at the same time!");
             }
-            self.regex = Some(regex);
+            self.regex = Some(NamePattern::Str(regex));
             self
         }
     }
 
+    /// Sets a byte-oriented path name regex to search for, in place of [`Self::regex`].
+    ///
+    /// On Unix the regex is matched against the raw bytes of each file name, so names that are
+    /// not valid UTF-8 can be found.
+    ///
+    /// # Panics
+    ///
+    /// If a `cwd` (aka current working directory) or `binary_name` was set previously, this will
+    /// panic, as those options are incompatible with regex searches.
+    #[cfg(feature = "regex")]
+    pub fn regex_bytes(mut self, regex: regex::bytes::Regex) -> Self {
+        if self.cwd != Some(either::Either::Left(false)) && self.cwd.is_some() {
+            panic!("which can't use regex and cwd at the same time!")
+        }
+        if self.binary_name.is_some() {
+            panic!("which can't use `binary_name` and `regex` at the same time!");
+        }
+        self.regex = Some(NamePattern::Bytes(regex));
+        self
+    }
+
     /// Sets the path name to search for. You ***MUST*** call this, or [`Self::regex`] prior to searching.
     ///
     /// # Panics
@@ -386,62 +819,312 @@ impl WhichConfig {
         self
     }
 
-    /// Uses the given string instead of the `PATH` env variable.
+    /// Uses the given string instead of the `PATH` env variable. Its entries are tagged `custom`.
     pub fn custom_path_list(mut self, custom_path_list: OsString) -> Self {
-        self.custom_path_list = Some(custom_path_list);
+        self.search_path = Some(SearchPath::new().append_path_list("custom", custom_path_list));
+        self
+    }
+
+    /// Searches the directories of `search_path` instead of the `PATH` env variable.
+    pub fn search_path(mut self, search_path: SearchPath) -> Self {
+        self.search_path = Some(search_path);
+        self
+    }
+
+    /// Searches the project-local bin directories found by walking up from the working directory
+    /// before the path list. Has no effect without a working directory, as with
+    /// `system_cwd(false)`.
+    pub fn project_bins(mut self, project_bins: ProjectBins) -> Self {
+        self.project_bins = Some(project_bins);
         self
     }
 
     /// Uses the `PATH` env variable. Enabled by default.
     pub fn system_path_list(mut self) -> Self {
-        self.custom_path_list = None;
+        self.search_path = None;
         self
     }
 
-    /// Finishes configuring, runs the query and returns the first result.
-    pub async fn first_result(self) -> Result<path::PathBuf> {
-        let mut candidates = self.all_results().take_while(|x| future::ready(x.is_ok()));
-        pin!(candidates)
-            .next()
-            .await
-            .unwrap_or(Err(Error::CannotFindBinaryPath))
+    /// Requires candidates to also be accepted by `checker`, after the default existence and
+    /// executable checks. May be called several times; checkers run in the order added.
+    pub fn add_checker(mut self, checker: impl Checker + 'static) -> Self {
+        self.checkers.push(Box::new(checker));
+        self
     }
 
-    /// Finishes configuring, runs the query and returns all results.
-    pub fn all_results(self) -> impl Stream<Item = Result<path::PathBuf>> {
-        let binary_checker = build_binary_checker();
+    /// Sets how checkers are run against each candidate. [`Evaluation::Sequential`] by default.
+    pub fn evaluation(mut self, evaluation: Evaluation) -> Self {
+        self.evaluation = evaluation;
+        self
+    }
 
-        let finder = Finder::new();
+    /// Sets which identity candidates must be executable by. [`AccessMode::Real`] by default;
+    /// setuid or otherwise elevated processes usually want [`AccessMode::Effective`].
+    pub fn access_mode(mut self, access_mode: AccessMode) -> Self {
+        self.access_mode = access_mode;
+        self
+    }
 
-        let paths = match self.custom_path_list.or_else(|| env::var_os("PATH")) {
-            Some(x) => x,
-            None => {
-                return stream::once(future::ready(Err(Error::CannotFindBinaryPath))).boxed_local();
-            }
-        };
+    /// Checks candidates that are symbolic links against `policy`, using a [`SymlinkChecker`].
+    /// Links are followed without further checks by default.
+    pub fn symlink_policy(mut self, policy: SymlinkPolicy) -> Self {
+        self.symlink_policy = Some(policy);
+        self
+    }
 
+    /// Whether to reject candidates that an untrusted user could have planted or replaced, using
+    /// a [`SecureChecker`]. `false` by default.
+    pub fn secure(mut self, secure: bool) -> Self {
+        self.secure = secure;
+        self
+    }
+
+    /// Only accept binaries whose reported version satisfies `req`, checked after every other
+    /// checker by running the candidate with `--version`. Add a
+    /// [`VersionChecker`](checker::VersionChecker) instead to change how the version is probed.
+    #[cfg(feature = "version")]
+    pub fn version_req(mut self, req: VersionReq) -> Self {
+        self.version_req = Some(req);
+        self
+    }
+
+    /// Whether regex searches yield an [`Error::UnreadableDirectory`] item for each directory
+    /// that cannot be read, instead of skipping it. `false` by default.
+    pub fn report_unreadable_dirs(mut self, report: bool) -> Self {
+        self.report_unreadable_dirs = report;
+        self
+    }
+
+    /// Finishes configuring, runs the query and returns the first result.
+    pub async fn first_result(mut self) -> Result<path::PathBuf> {
         #[cfg(feature = "regex")]
-        if let Some(regex) = self.regex {
-            return finder.find_re(regex, paths, binary_checker).boxed_local();
+        if let Some(regex) = &self.regex {
+            let name = OsString::from(regex.as_str());
+            let search_paths = self
+                .search_path
+                .clone()
+                .or_else(system_search_path)
+                .map(|p| p.dirs().map(path::Path::to_path_buf).collect())
+                .unwrap_or_default();
+            // Keep looking past unreadable directories, and only report one if nothing matched.
+            let mut results = pin!(self.all_results());
+            let mut first_error = None;
+            while let Some(result) = results.next().await {
+                match result {
+                    Ok(path) => return Ok(path),
+                    Err(error) => {
+                        first_error.get_or_insert(error);
+                    }
+                }
+            }
+            return Err(first_error.unwrap_or(Error::CannotFindBinaryPath { name, search_paths }));
         }
 
-        let cwd = match self.cwd {
-            Some(either::Either::Left(false)) => None,
-            Some(either::Either::Right(custom)) => Some(custom),
-            None | Some(either::Either::Left(true)) => env::current_dir().ok(),
-        };
+        let binary_checker = self.binary_checker();
+
+        let finder = Finder::new();
+
+        let cwd = self.cwd();
+        let paths = resolve_search_path(self.search_path, self.project_bins, cwd.clone()).await;
 
         finder
-            .find(
+            .find_first(
                 self.binary_name.expect(
                     "binary_name not set! You must set binary_name or regex before searching!",
                 ),
-                Some(paths),
+                paths,
                 cwd,
                 binary_checker,
             )
+            .await
+    }
+
+    /// Finishes configuring, probes every candidate and reports the outcome of each check.
+    ///
+    /// # Panics
+    ///
+    /// If `binary_name` was not set, this will panic. Regex searches cannot be explained.
+    pub async fn explain(mut self) -> Result<SearchReport> {
+        let binary_checker = self.binary_checker();
+
+        let finder = Finder::new();
+
+        let cwd = self.cwd();
+        let paths = resolve_search_path(self.search_path, self.project_bins, cwd.clone()).await;
+
+        finder
+            .explain(
+                self.binary_name
+                    .expect("binary_name not set! You must set binary_name before explaining!"),
+                paths,
+                cwd,
+                binary_checker,
+            )
+            .await
+    }
+
+    /// Finishes configuring, finds the first result and every later result it shadows.
+    ///
+    /// # Panics
+    ///
+    /// If `binary_name` was not set, this will panic. Regex searches are not supported.
+    pub async fn shadowed(mut self) -> Result<Shadowing> {
+        let binary_checker = self.binary_checker();
+
+        let finder = Finder::new();
+
+        let cwd = self.cwd();
+        let paths = resolve_search_path(self.search_path, self.project_bins, cwd.clone()).await;
+
+        finder
+            .find_shadowed(
+                self.binary_name
+                    .expect("binary_name not set! You must set binary_name before searching!"),
+                paths,
+                cwd,
+                binary_checker,
+            )
+            .await
+    }
+
+    /// Finishes configuring, runs the query and returns the first result along with the search
+    /// path entry it was found in.
+    ///
+    /// # Panics
+    ///
+    /// If `binary_name` was not set, this will panic. Regex searches are not supported.
+    pub async fn first_sourced(mut self) -> Result<SourcedPath> {
+        let binary_checker = self.binary_checker();
+
+        let finder = Finder::new();
+
+        let cwd = self.cwd();
+        let paths = resolve_search_path(self.search_path, self.project_bins, cwd.clone()).await;
+        let entries = paths.as_ref().map(|p| p.entries().to_vec());
+
+        finder
+            .find_first_located(
+                self.binary_name
+                    .expect("binary_name not set! You must set binary_name before searching!"),
+                paths,
+                cwd,
+                binary_checker,
+            )
+            .await
+            .map(|located| sourced(located, entries.as_deref()))
+    }
+
+    /// Finishes configuring, runs the query and returns all results along with the search path
+    /// entry each was found in.
+    ///
+    /// # Panics
+    ///
+    /// If `binary_name` was not set, this will panic. Regex searches are not supported.
+    pub fn all_sourced(mut self) -> impl Stream<Item = Result<SourcedPath>> {
+        let binary_checker = self.binary_checker();
+
+        let finder = Finder::new();
+
+        let cwd = self.cwd();
+        let paths = resolve_search_path(self.search_path, self.project_bins, cwd.clone());
+        let name = self
+            .binary_name
+            .expect("binary_name not set! You must set binary_name before searching!");
+
+        stream::once(async move {
+            let paths = paths.await;
+            let entries = paths.as_ref().map(|p| p.entries().to_vec());
+            finder
+                .find_located(name, paths, cwd, binary_checker)
+                .map_ok(move |located| sourced(located, entries.as_deref()))
+        })
+        .flatten()
+    }
+
+    /// Finishes configuring, runs the query and returns all results.
+    pub fn all_results(mut self) -> impl Stream<Item = Result<path::PathBuf>> {
+        let binary_checker = self.binary_checker();
+
+        let finder = Finder::new();
+
+        #[cfg(feature = "regex")]
+        if let Some(regex) = self.regex {
+            return match self.search_path.take().or_else(system_search_path) {
+                Some(paths) => finder
+                    .find_re(regex, paths, binary_checker, self.report_unreadable_dirs)
+                    .boxed_local(),
+                None => {
+                    let name = OsString::from(regex.as_str());
+                    stream::once(future::ready(Err(Error::PathUnset { name }))).boxed_local()
+                }
+            };
+        }
+
+        let cwd = self.cwd();
+        let paths = resolve_search_path(self.search_path, self.project_bins, cwd.clone());
+        let name = self
+            .binary_name
+            .expect("binary_name not set! You must set binary_name or regex before searching!");
+
+        stream::once(async move { finder.find(name, paths.await, cwd, binary_checker) })
+            .flatten()
             .boxed_local()
     }
+
+    /// Finishes configuring, runs the query and returns all results along with candidates that
+    /// exist but were rejected.
+    ///
+    /// # Panics
+    ///
+    /// If `binary_name` was not set, this will panic. Regex searches do not report near misses.
+    pub fn all_results_with_near_misses(mut self) -> impl Stream<Item = Result<Lookup>> {
+        let binary_checker = self.binary_checker();
+
+        let finder = Finder::new();
+
+        let cwd = self.cwd();
+        let paths = resolve_search_path(self.search_path, self.project_bins, cwd.clone());
+        let name = self.binary_name.expect(
+            "binary_name not set! You must set binary_name before searching for near misses!",
+        );
+
+        stream::once(
+            async move { finder.find_with_near_misses(name, paths.await, cwd, binary_checker) },
+        )
+        .flatten()
+    }
+
+    fn binary_checker(&mut self) -> CompositeChecker {
+        let mut binary_checker = CompositeChecker::new()
+            .add_checker(Box::new(ExistedChecker::new()))
+            .add_checker(Box::new(
+                ExecutableChecker::new().access_mode(self.access_mode),
+            ));
+        if let Some(policy) = self.symlink_policy.take() {
+            binary_checker = binary_checker.add_checker(Box::new(SymlinkChecker::new(policy)));
+        }
+        if self.secure {
+            binary_checker = binary_checker.add_checker(Box::new(SecureChecker::new()));
+        }
+        binary_checker = self
+            .checkers
+            .drain(..)
+            .fold(binary_checker, CompositeChecker::add_checker);
+        #[cfg(feature = "version")]
+        if let Some(req) = self.version_req.take() {
+            binary_checker =
+                binary_checker.add_checker(Box::new(checker::VersionChecker::new(req)));
+        }
+        binary_checker.evaluation(self.evaluation)
+    }
+
+    fn cwd(&self) -> Option<path::PathBuf> {
+        match &self.cwd {
+            Some(either::Either::Left(false)) => None,
+            Some(either::Either::Right(custom)) => Some(custom.clone()),
+            None | Some(either::Either::Left(true)) => env::current_dir().ok(),
+        }
+    }
 }
 
 /// An owned, immutable wrapper around a `PathBuf` containing the path of an executable.
@@ -515,6 +1198,16 @@ impl Path {
     pub fn into_path_buf(self) -> path::PathBuf {
         self.inner
     }
+
+    /// Reads the start of the executable and classifies its format.
+    pub async fn binary_kind(&self) -> io::Result<BinaryKind> {
+        binary_kind(&self.inner).await
+    }
+
+    /// Follows the symbolic links at this path.
+    pub async fn symlink_chain(&self) -> io::Result<SymlinkChain> {
+        symlink_chain(&self.inner).await
+    }
 }
 
 impl fmt::Debug for Path {
@@ -580,7 +1273,7 @@ impl CanonicalPath {
     pub async fn new<T: AsRef<OsStr>>(binary_name: T) -> Result<CanonicalPath> {
         which(binary_name)
             .await
-            .and_then(|p| p.canonicalize().map_err(|_| Error::CannotCanonicalize))
+            .and_then(canonicalize)
             .map(|inner| CanonicalPath { inner })
     }
 
@@ -589,12 +1282,9 @@ impl CanonicalPath {
     /// This calls `which_all` and `Path::canonicalize` and maps the results into `CanonicalPath`s.
     pub fn all<T: AsRef<OsStr>>(binary_name: T) -> impl Stream<Item = Result<CanonicalPath>> {
         which_all(binary_name).map(|inner| {
-            inner.and_then(|inner| {
-                inner
-                    .canonicalize()
-                    .map_err(|_| Error::CannotCanonicalize)
-                    .map(|inner| CanonicalPath { inner })
-            })
+            inner
+                .and_then(canonicalize)
+                .map(|inner| CanonicalPath { inner })
         })
     }
 
@@ -612,11 +1302,9 @@ impl CanonicalPath {
             let canonical = if cfg!(target_os = "wasi") {
                 Ok(inner)
             } else {
-                inner.canonicalize()
+                canonicalize(inner)
             };
-            canonical
-                .map_err(|_| Error::CannotCanonicalize)
-                .map(|inner| CanonicalPath { inner })
+            canonical.map(|inner| CanonicalPath { inner })
         })
     }
 
@@ -639,11 +1327,9 @@ impl CanonicalPath {
                 let canonical = if cfg!(target_os = "wasi") {
                     Ok(inner)
                 } else {
-                    inner.canonicalize()
+                    canonicalize(inner)
                 };
-                canonical
-                    .map_err(|_| Error::CannotCanonicalize)
-                    .map(|inner| CanonicalPath { inner })
+                canonical.map(|inner| CanonicalPath { inner })
             })
         })
     }
@@ -657,6 +1343,11 @@ impl CanonicalPath {
     pub fn into_path_buf(self) -> path::PathBuf {
         self.inner
     }
+
+    /// Reads the start of the executable and classifies its format.
+    pub async fn binary_kind(&self) -> io::Result<BinaryKind> {
+        binary_kind(&self.inner).await
+    }
 }
 
 impl fmt::Debug for CanonicalPath {
@@ -0,0 +1,27 @@
+use std::path::{Path, PathBuf};
+
+use crate::verdict::Rejection;
+
+/// A candidate seen during a search that reports near misses.
+///
+/// Candidates where nothing exists are skipped; anything else that exists is reported, so
+/// callers can tell "not found" apart from e.g. "found but missing `chmod +x`".
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Lookup {
+    /// The candidate passed every check.
+    Found(PathBuf),
+    /// Something exists at `path`, but it was rejected for `reason`.
+    NearMiss { path: PathBuf, reason: Rejection },
+}
+
+impl Lookup {
+    pub fn path(&self) -> &Path {
+        match self {
+            Lookup::Found(path) | Lookup::NearMiss { path, .. } => path,
+        }
+    }
+
+    pub fn is_found(&self) -> bool {
+        matches!(self, Lookup::Found(_))
+    }
+}
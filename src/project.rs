@@ -0,0 +1,181 @@
+use std::path::{Path, PathBuf};
+
+use crate::search_path::SearchPath;
+
+/// The source tag of project-local directories.
+const PROJECT_SOURCE: &str = "project";
+
+/// Files or directories that mark the root of a version-controlled checkout.
+const VCS_MARKERS: &[&str] = &[".git", ".hg", ".svn", ".jj"];
+
+/// Where [`ProjectBins`] stops walking up from the working directory.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum ProjectBoundary {
+    /// Stop at the nearest directory holding `.git`, `.hg`, `.svn` or `.jj`, after searching it.
+    /// Walks up to the filesystem root outside a checkout.
+    #[default]
+    VcsRoot,
+    /// Stop at this directory, after searching it. Nothing is searched if the working directory
+    /// is not inside it.
+    Dir(PathBuf),
+    /// Walk up to the filesystem root.
+    None,
+}
+
+impl ProjectBoundary {
+    /// The directories from `cwd` up to this boundary, nearest first. `cwd` and a
+    /// [`Dir`](ProjectBoundary::Dir) boundary are canonicalized first, so relative and symlinked
+    /// paths compare equal to the directories they point at.
+    pub(crate) async fn walk(&self, cwd: &Path) -> Vec<PathBuf> {
+        let cwd = canonicalize(cwd).await;
+        let boundary = match self {
+            ProjectBoundary::Dir(boundary) => Some(canonicalize(boundary).await),
+            _ => None,
+        };
+        let mut dirs = Vec::new();
+        for dir in cwd.ancestors() {
+            if let Some(boundary) = &boundary {
+                if !dir.starts_with(boundary) {
+                    break;
+                }
+            }
+            dirs.push(dir.to_path_buf());
+            if *self == ProjectBoundary::VcsRoot && is_vcs_root(dir).await {
+                break;
+            }
+        }
+        dirs
+    }
+}
+
+/// A project-local bin directory, searched in every directory walked through.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct BinDir {
+    relative: PathBuf,
+    /// Only search `relative` in directories holding this file.
+    marker: Option<PathBuf>,
+}
+
+/// Project-local bin directories, such as `node_modules/.bin`, found by walking up from the
+/// working directory as `npx` does.
+///
+/// Pass to [`WhichConfig::project_bins`](crate::WhichConfig::project_bins) to search them before
+/// `PATH`. Nearer directories come first, so the nearest package wins.
+///
+/// # Example
+///
+/// ```no_run
+/// # tokio_test::block_on(async {
+/// use async_which::{ProjectBins, WhichConfig};
+///
+/// let eslint = WhichConfig::new()
+///     .binary_name("eslint".into())
+///     .custom_cwd("/repo/packages/web/src".into())
+///     .project_bins(ProjectBins::new())
+///     .first_result()
+///     .await
+///     .unwrap();
+/// # })
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProjectBins {
+    dirs: Vec<BinDir>,
+    boundary: ProjectBoundary,
+}
+
+impl Default for ProjectBins {
+    fn default() -> Self {
+        let venv = if cfg!(windows) {
+            ".venv/Scripts"
+        } else {
+            ".venv/bin"
+        };
+        ProjectBins::empty()
+            .dir("node_modules/.bin")
+            .dir(venv)
+            .dir("vendor/bin")
+            .marked_dir("Gemfile", "bin")
+    }
+}
+
+impl ProjectBins {
+    /// Searches `node_modules/.bin`, `.venv/bin` (`.venv/Scripts` on Windows), `vendor/bin`, and
+    /// `bin` next to a `Gemfile`, up to the [VCS root](ProjectBoundary::VcsRoot).
+    pub fn new() -> ProjectBins {
+        ProjectBins::default()
+    }
+
+    /// Searches no directories until some are added.
+    pub fn empty() -> ProjectBins {
+        ProjectBins {
+            dirs: Vec::new(),
+            boundary: ProjectBoundary::default(),
+        }
+    }
+
+    /// Also searches `relative` in every directory walked through.
+    pub fn dir(mut self, relative: impl Into<PathBuf>) -> Self {
+        self.dirs.push(BinDir {
+            relative: relative.into(),
+            marker: None,
+        });
+        self
+    }
+
+    /// Also searches `relative` in directories holding `marker`, e.g. `bin` next to a `Gemfile`.
+    pub fn marked_dir(mut self, marker: impl Into<PathBuf>, relative: impl Into<PathBuf>) -> Self {
+        self.dirs.push(BinDir {
+            relative: relative.into(),
+            marker: Some(marker.into()),
+        });
+        self
+    }
+
+    /// Sets where the walk stops. [`ProjectBoundary::VcsRoot`] by default.
+    pub fn boundary(mut self, boundary: ProjectBoundary) -> Self {
+        self.boundary = boundary;
+        self
+    }
+
+    /// The bin directories that exist from `cwd` up to the boundary, nearest first, tagged
+    /// `project`.
+    pub async fn search_path<P: AsRef<Path>>(&self, cwd: P) -> SearchPath {
+        let mut search_path = SearchPath::new();
+        for dir in self.boundary.walk(cwd.as_ref()).await {
+            for bin in &self.dirs {
+                if let Some(marker) = &bin.marker {
+                    if !exists(&dir.join(marker)).await {
+                        continue;
+                    }
+                }
+                let candidate = dir.join(&bin.relative);
+                if tokio::fs::metadata(&candidate)
+                    .await
+                    .is_ok_and(|metadata| metadata.is_dir())
+                {
+                    search_path = search_path.append_dir(PROJECT_SOURCE, candidate);
+                }
+            }
+        }
+        search_path
+    }
+}
+
+async fn is_vcs_root(dir: &Path) -> bool {
+    for marker in VCS_MARKERS {
+        if exists(&dir.join(marker)).await {
+            return true;
+        }
+    }
+    false
+}
+
+async fn canonicalize(path: &Path) -> PathBuf {
+    tokio::fs::canonicalize(path)
+        .await
+        .unwrap_or_else(|_| path.to_path_buf())
+}
+
+async fn exists(path: &Path) -> bool {
+    tokio::fs::symlink_metadata(path).await.is_ok()
+}
@@ -0,0 +1,261 @@
+use std::env;
+use std::ffi::{OsStr, OsString};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::error::{Error, Result};
+use crate::finder::Finder;
+use crate::project::ProjectBoundary;
+use crate::search_path::SearchPath;
+
+/// Directory names checked for an inactive virtual environment, in order.
+const VENV_DIRS: &[&str] = &[".venv", "venv"];
+
+/// What kind of Python environment a [`PythonEnv`] is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PythonEnvKind {
+    /// A `venv` or `virtualenv` environment, with a `pyvenv.cfg` file.
+    Virtualenv,
+    /// A conda environment.
+    Conda,
+}
+
+impl fmt::Display for PythonEnvKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PythonEnvKind::Virtualenv => write!(f, "virtualenv"),
+            PythonEnvKind::Conda => write!(f, "conda"),
+        }
+    }
+}
+
+/// Why a [`PythonEnv`] was used.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PythonEnvSource {
+    /// The `VIRTUAL_ENV` env variable, set by activating a virtual environment.
+    VirtualEnvVar,
+    /// The `CONDA_PREFIX` env variable, set by activating a conda environment.
+    CondaPrefixVar,
+    /// A `.venv` or `venv` directory found by walking up from the working directory.
+    Discovered,
+}
+
+impl fmt::Display for PythonEnvSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PythonEnvSource::VirtualEnvVar => write!(f, "activated through VIRTUAL_ENV"),
+            PythonEnvSource::CondaPrefixVar => write!(f, "activated through CONDA_PREFIX"),
+            PythonEnvSource::Discovered => write!(f, "discovered"),
+        }
+    }
+}
+
+/// A Python environment whose scripts directory is searched before `PATH`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PythonEnv {
+    pub prefix: PathBuf,
+    pub kind: PythonEnvKind,
+    pub source: PythonEnvSource,
+}
+
+impl PythonEnv {
+    /// The directories holding the interpreter and console scripts: `bin` on Unix, and
+    /// `Scripts` (plus the prefix itself for conda) on Windows.
+    pub fn bin_dirs(&self) -> Vec<PathBuf> {
+        if !cfg!(windows) {
+            return vec![self.prefix.join("bin")];
+        }
+        match self.kind {
+            PythonEnvKind::Virtualenv => vec![self.prefix.join("Scripts")],
+            PythonEnvKind::Conda => vec![self.prefix.clone(), self.prefix.join("Scripts")],
+        }
+    }
+}
+
+impl fmt::Display for PythonEnv {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} ({})",
+            self.kind,
+            self.prefix.display(),
+            self.source
+        )
+    }
+}
+
+/// A tool found by [`Python::which`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PythonTool {
+    pub path: PathBuf,
+    /// The environment `path` was found in, or `None` if it came from `PATH`.
+    pub env: Option<PythonEnv>,
+}
+
+/// Resolves Python interpreters and console scripts, preferring the project's environment over
+/// whatever comes first on `PATH`.
+///
+/// The environment is, in order: the one in `VIRTUAL_ENV`, the one in `CONDA_PREFIX`, then the
+/// nearest `.venv` or `venv` holding a `pyvenv.cfg`, walking up from the working directory.
+///
+/// # Example
+///
+/// ```no_run
+/// # tokio_test::block_on(async {
+/// use async_which::Python;
+///
+/// let pytest = Python::from_env().unwrap().which("pytest").await.unwrap();
+/// match &pytest.env {
+///     Some(env) => println!("{} from {}", pytest.path.display(), env),
+///     None => println!("{} from PATH", pytest.path.display()),
+/// }
+/// # })
+/// ```
+#[derive(Clone, Debug)]
+pub struct Python {
+    cwd: PathBuf,
+    virtual_env: Option<PathBuf>,
+    conda_prefix: Option<PathBuf>,
+    path_list: Option<OsString>,
+    discover: bool,
+    boundary: ProjectBoundary,
+}
+
+impl Python {
+    /// Resolves for `cwd`, ignoring the environment: only discovered virtual environments are
+    /// searched until more is configured.
+    pub fn new(cwd: impl Into<PathBuf>) -> Python {
+        Python {
+            cwd: cwd.into(),
+            virtual_env: None,
+            conda_prefix: None,
+            path_list: None,
+            discover: true,
+            boundary: ProjectBoundary::default(),
+        }
+    }
+
+    /// Resolves for the current directory, honouring `VIRTUAL_ENV`, `CONDA_PREFIX` and `PATH`.
+    pub fn from_env() -> Result<Python> {
+        let cwd = env::current_dir().map_err(|source| Error::CannotGetCurrentDir { source })?;
+        let var = |name| env::var_os(name).filter(|value| !value.is_empty());
+        let mut python = Python::new(cwd);
+        python.virtual_env = var("VIRTUAL_ENV").map(PathBuf::from);
+        python.conda_prefix = var("CONDA_PREFIX").map(PathBuf::from);
+        python.path_list = var("PATH");
+        Ok(python)
+    }
+
+    /// Uses the virtual environment at `prefix`, as if it were in `VIRTUAL_ENV`.
+    pub fn virtual_env(mut self, prefix: impl Into<PathBuf>) -> Self {
+        self.virtual_env = Some(prefix.into());
+        self
+    }
+
+    /// Uses the conda environment at `prefix`, as if it were in `CONDA_PREFIX`.
+    pub fn conda_prefix(mut self, prefix: impl Into<PathBuf>) -> Self {
+        self.conda_prefix = Some(prefix.into());
+        self
+    }
+
+    /// Falls back to the directories in `path_list`, separated like the `PATH` env variable.
+    pub fn path_list(mut self, path_list: impl Into<OsString>) -> Self {
+        self.path_list = Some(path_list.into());
+        self
+    }
+
+    /// Whether to look for an inactive `.venv` or `venv`. `true` by default.
+    pub fn discover(mut self, discover: bool) -> Self {
+        self.discover = discover;
+        self
+    }
+
+    /// Sets where the search for an inactive environment stops. [`ProjectBoundary::VcsRoot`] by
+    /// default.
+    pub fn boundary(mut self, boundary: ProjectBoundary) -> Self {
+        self.boundary = boundary;
+        self
+    }
+
+    /// The environment tools are looked up in first, if any.
+    pub async fn env(&self) -> Option<PythonEnv> {
+        if let Some(prefix) = &self.virtual_env {
+            return Some(PythonEnv {
+                prefix: prefix.clone(),
+                kind: PythonEnvKind::Virtualenv,
+                source: PythonEnvSource::VirtualEnvVar,
+            });
+        }
+        if let Some(prefix) = &self.conda_prefix {
+            return Some(PythonEnv {
+                prefix: prefix.clone(),
+                kind: PythonEnvKind::Conda,
+                source: PythonEnvSource::CondaPrefixVar,
+            });
+        }
+        if !self.discover {
+            return None;
+        }
+
+        for dir in self.boundary.walk(&self.cwd).await {
+            for name in VENV_DIRS {
+                let prefix = dir.join(name);
+                if is_file(&prefix.join("pyvenv.cfg")).await {
+                    return Some(PythonEnv {
+                        prefix,
+                        kind: PythonEnvKind::Virtualenv,
+                        source: PythonEnvSource::Discovered,
+                    });
+                }
+            }
+        }
+        None
+    }
+
+    /// Finds `tool` in the environment, then in the path list.
+    pub async fn which<T: AsRef<OsStr>>(&self, tool: T) -> Result<PythonTool> {
+        let env = self.env().await;
+        let mut search_path = match &env {
+            Some(env) => SearchPath::new().append_dirs(env.kind.to_string(), env.bin_dirs()),
+            None => SearchPath::new(),
+        };
+        let env_entries = search_path.len();
+        if let Some(path_list) = &self.path_list {
+            search_path = search_path.append_path_list("PATH", path_list);
+        }
+
+        let located = Finder::new()
+            .find_first_located(
+                tool,
+                Some(search_path),
+                Option::<&Path>::None,
+                crate::build_binary_checker(),
+            )
+            .await?;
+        let in_env = located.path_index.is_some_and(|index| index < env_entries);
+        Ok(PythonTool {
+            path: located.path,
+            env: env.filter(|_| in_env),
+        })
+    }
+
+    /// Finds the interpreter, `python` or else `python3`, as in [`Self::which`]. Either one in
+    /// the environment wins over both in the path list.
+    pub async fn interpreter(&self) -> Result<PythonTool> {
+        let python = self.which("python").await;
+        if matches!(&python, Ok(tool) if tool.env.is_some()) {
+            return python;
+        }
+        match self.which("python3").await {
+            Ok(python3) if python3.env.is_some() => Ok(python3),
+            _ if python.is_ok() => python,
+            python3 => python3,
+        }
+    }
+}
+
+async fn is_file(path: &Path) -> bool {
+    tokio::fs::metadata(path)
+        .await
+        .is_ok_and(|metadata| metadata.is_file())
+}
@@ -0,0 +1,90 @@
+use std::ffi::OsString;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::verdict::Verdict;
+
+/// A record of every candidate probed while looking up a binary.
+///
+/// Returned by [`which_explain`](crate::which_explain) and
+/// [`WhichConfig::explain`](crate::WhichConfig::explain).
+#[derive(Clone, Debug)]
+pub struct SearchReport {
+    /// The name that was looked up.
+    pub name: OsString,
+    /// The directories that were searched, in order.
+    pub search_paths: Vec<PathBuf>,
+    /// Every candidate path that was generated, in the order it was probed.
+    pub probes: Vec<Probe>,
+}
+
+impl SearchReport {
+    /// The first accepted candidate, i.e. what `which` would have returned.
+    pub fn found(&self) -> Option<&Path> {
+        self.probes
+            .iter()
+            .find(|probe| probe.accepted())
+            .map(|probe| probe.candidate.as_path())
+    }
+}
+
+/// A single candidate path and the outcome of each checker against it.
+#[derive(Clone, Debug)]
+pub struct Probe {
+    pub candidate: PathBuf,
+    /// Index into [`SearchReport::search_paths`] of the entry this candidate was built from.
+    /// `None` when the candidate was resolved against the current working directory.
+    pub path_index: Option<usize>,
+    pub checks: Vec<CheckOutcome>,
+}
+
+impl Probe {
+    /// Whether every checker accepted this candidate.
+    pub fn accepted(&self) -> bool {
+        self.checks.iter().all(|check| check.verdict.is_accepted())
+    }
+}
+
+/// The verdict of one checker on one candidate.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CheckOutcome {
+    /// The name of the checker that produced this outcome.
+    pub checker: String,
+    pub verdict: Verdict,
+}
+
+impl fmt::Display for SearchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "search for {:?} in {} location(s)",
+            self.name,
+            self.search_paths.len()
+        )?;
+        for probe in &self.probes {
+            match probe.path_index {
+                Some(index) => write!(f, "  [{}] ", index)?,
+                None => write!(f, "  [cwd] ")?,
+            }
+            write!(f, "{}: ", probe.candidate.display())?;
+            if probe.accepted() {
+                write!(f, "accepted")?;
+            } else {
+                write!(f, "rejected")?;
+            }
+            for (i, check) in probe.checks.iter().enumerate() {
+                let separator = if i == 0 { " (" } else { ", " };
+                let verdict = match &check.verdict {
+                    Verdict::Accepted => "ok".to_owned(),
+                    Verdict::Rejected(reason) => reason.to_string(),
+                };
+                write!(f, "{}{}: {}", separator, check.checker, verdict)?;
+            }
+            if !probe.checks.is_empty() {
+                write!(f, ")")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,313 @@
+use std::env;
+use std::ffi::OsStr;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use toml::{Table, Value};
+
+use crate::error::{Error, Result};
+use crate::finder::Finder;
+use crate::search_path::SearchPath;
+use crate::sysroot::{running_host_triple, Sysroot};
+
+/// The env variable that overrides the toolchain for every directory.
+const TOOLCHAIN_VAR: &str = "RUSTUP_TOOLCHAIN";
+
+/// Toolchain files, in the order rustup looks for them in each directory.
+const TOOLCHAIN_FILES: &[&str] = &["rust-toolchain", "rust-toolchain.toml"];
+
+/// Why a [`Toolchain`] was chosen, in rustup's order of precedence.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ToolchainSource {
+    /// The `RUSTUP_TOOLCHAIN` env variable, or [`Rustup::toolchain_override`].
+    Environment,
+    /// A `rustup override set` entry for this directory in `settings.toml`.
+    Override(PathBuf),
+    /// A `rust-toolchain` or `rust-toolchain.toml` file.
+    ToolchainFile(PathBuf),
+    /// The `default_toolchain` in `settings.toml`.
+    Default,
+}
+
+impl fmt::Display for ToolchainSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ToolchainSource::Environment => {
+                write!(f, "overridden by environment variable {}", TOOLCHAIN_VAR)
+            }
+            ToolchainSource::Override(dir) => {
+                write!(f, "directory override for '{}'", dir.display())
+            }
+            ToolchainSource::ToolchainFile(file) => {
+                write!(f, "overridden by '{}'", file.display())
+            }
+            ToolchainSource::Default => write!(f, "default"),
+        }
+    }
+}
+
+/// An installed rustup toolchain.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Toolchain {
+    /// The full name, e.g. `stable-x86_64-unknown-linux-gnu`, or the path of a toolchain
+    /// given by path in a toolchain file.
+    pub name: String,
+    /// The directory holding the toolchain, with its binaries in `bin`.
+    pub dir: PathBuf,
+    pub source: ToolchainSource,
+}
+
+impl Toolchain {
+    /// The toolchain as a [`Sysroot`], to find tools such as `rust-lld` inside it.
+    pub fn sysroot(&self) -> Sysroot {
+        Sysroot::new(&self.dir)
+    }
+}
+
+impl fmt::Display for Toolchain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.name, self.source)
+    }
+}
+
+/// A tool found in a rustup toolchain, as returned by [`Rustup::which`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RustupTool {
+    /// The binary inside the toolchain, rather than the proxy in `~/.cargo/bin`.
+    pub path: PathBuf,
+    pub toolchain: Toolchain,
+}
+
+/// Resolves tools to the binaries of the toolchain rustup would pick for a directory.
+///
+/// The toolchain is chosen as rustup does: `RUSTUP_TOOLCHAIN` first, then, walking up from
+/// the directory, `rustup override set` entries and `rust-toolchain(.toml)` files, then the
+/// default toolchain.
+///
+/// # Example
+///
+/// ```no_run
+/// # tokio_test::block_on(async {
+/// use async_which::Rustup;
+///
+/// let clippy = Rustup::from_env()
+///     .unwrap()
+///     .cwd("/home/me/project")
+///     .which("cargo-clippy")
+///     .await
+///     .unwrap();
+/// println!("{} from {}", clippy.path.display(), clippy.toolchain);
+/// # })
+/// ```
+#[derive(Clone, Debug)]
+pub struct Rustup {
+    home: PathBuf,
+    cwd: PathBuf,
+    toolchain: Option<String>,
+}
+
+impl Rustup {
+    /// Resolves against the rustup installation in `home` (e.g. `~/.rustup`) for `cwd`, ignoring
+    /// the environment.
+    pub fn new(home: impl Into<PathBuf>, cwd: impl Into<PathBuf>) -> Rustup {
+        Rustup {
+            home: home.into(),
+            cwd: cwd.into(),
+            toolchain: None,
+        }
+    }
+
+    /// Resolves against `RUSTUP_HOME`, or `.rustup` in the home directory, for the current
+    /// directory, honouring `RUSTUP_TOOLCHAIN`.
+    pub fn from_env() -> Result<Rustup> {
+        let home = env::var_os("RUSTUP_HOME")
+            .filter(|home| !home.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| home_dir().map(|home| home.join(".rustup")))
+            .ok_or(Error::RustupHomeUnset)?;
+        let cwd = env::current_dir().map_err(|source| Error::CannotGetCurrentDir { source })?;
+        let rustup = Rustup::new(home, cwd);
+        Ok(match env::var(TOOLCHAIN_VAR) {
+            Ok(toolchain) if !toolchain.is_empty() => rustup.toolchain_override(toolchain),
+            _ => rustup,
+        })
+    }
+
+    /// Resolves for `cwd` instead.
+    pub fn cwd(mut self, cwd: impl Into<PathBuf>) -> Self {
+        self.cwd = cwd.into();
+        self
+    }
+
+    /// Uses `toolchain` for every directory, as `RUSTUP_TOOLCHAIN` does.
+    pub fn toolchain_override(mut self, toolchain: impl Into<String>) -> Self {
+        self.toolchain = Some(toolchain.into());
+        self
+    }
+
+    /// The toolchain rustup would use in the working directory.
+    pub async fn toolchain(&self) -> Result<Toolchain> {
+        let settings = self.settings().await?;
+        let host = settings
+            .get("default_host_triple")
+            .and_then(Value::as_str)
+            .map(str::to_owned)
+            .or_else(running_host_triple);
+
+        if let Some(name) = &self.toolchain {
+            return self
+                .installed(name, host.as_deref(), ToolchainSource::Environment)
+                .await;
+        }
+
+        let cwd = tokio::fs::canonicalize(&self.cwd)
+            .await
+            .unwrap_or_else(|_| self.cwd.clone());
+        let overrides = settings.get("overrides").and_then(Value::as_table);
+        for dir in cwd.ancestors() {
+            let overridden = overrides.and_then(|overrides| {
+                overrides
+                    .iter()
+                    .find(|(key, _)| Path::new(key) == dir)
+                    .and_then(|(_, name)| name.as_str())
+            });
+            if let Some(name) = overridden {
+                let source = ToolchainSource::Override(dir.to_path_buf());
+                return self.installed(name, host.as_deref(), source).await;
+            }
+
+            for file_name in TOOLCHAIN_FILES {
+                let file = dir.join(file_name);
+                let contents = match tokio::fs::read_to_string(&file).await {
+                    Ok(contents) => contents,
+                    Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                    Err(e) => return Err(invalid_config(&file, e)),
+                };
+                let source = ToolchainSource::ToolchainFile(file.clone());
+                return match parse_toolchain_file(&contents)
+                    .map_err(|e| invalid_config(&file, e))?
+                {
+                    ToolchainSpec::Channel(name) => {
+                        self.installed(&name, host.as_deref(), source).await
+                    }
+                    ToolchainSpec::Path(path) => Ok(Toolchain {
+                        name: path.clone(),
+                        dir: dir.join(path),
+                        source,
+                    }),
+                };
+            }
+        }
+
+        match settings.get("default_toolchain").and_then(Value::as_str) {
+            Some(name) => {
+                self.installed(name, host.as_deref(), ToolchainSource::Default)
+                    .await
+            }
+            None => Err(Error::ToolchainUnset { cwd }),
+        }
+    }
+
+    /// Finds `tool` in the `bin` directory of the toolchain rustup would use in the working
+    /// directory.
+    pub async fn which<T: AsRef<OsStr>>(&self, tool: T) -> Result<RustupTool> {
+        let toolchain = self.toolchain().await?;
+        let bin = SearchPath::new().append_dir(toolchain.name.as_str(), toolchain.dir.join("bin"));
+        let path = Finder::new()
+            .find_first(
+                tool,
+                Some(bin),
+                Option::<&Path>::None,
+                crate::build_binary_checker(),
+            )
+            .await?;
+        Ok(RustupTool { path, toolchain })
+    }
+
+    async fn settings(&self) -> Result<Table> {
+        let file = self.home.join("settings.toml");
+        match tokio::fs::read_to_string(&file).await {
+            Ok(contents) => contents
+                .parse::<Table>()
+                .map_err(|e| invalid_config(&file, e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Table::new()),
+            Err(e) => Err(invalid_config(&file, e)),
+        }
+    }
+
+    /// Finds the directory of the toolchain `name`, which may leave out the host triple.
+    async fn installed(
+        &self,
+        name: &str,
+        host: Option<&str>,
+        source: ToolchainSource,
+    ) -> Result<Toolchain> {
+        let toolchains = self.home.join("toolchains");
+        let mut names = vec![name.to_owned()];
+        if let Some(host) = host {
+            names.push(format!("{}-{}", name, host));
+        }
+        for name in &names {
+            let dir = toolchains.join(name);
+            if tokio::fs::metadata(&dir).await.is_ok_and(|m| m.is_dir()) {
+                return Ok(Toolchain {
+                    name: name.clone(),
+                    dir,
+                    source,
+                });
+            }
+        }
+        let name = names.pop().unwrap_or_default();
+        Err(Error::ToolchainNotInstalled {
+            dir: toolchains.join(&name),
+            name,
+        })
+    }
+}
+
+/// Finds `tool` in the toolchain rustup would use in the current directory, e.g.
+/// `~/.rustup/toolchains/stable-x86_64-unknown-linux-gnu/bin/cargo` for `cargo`.
+pub async fn which_rustup<T: AsRef<OsStr>>(tool: T) -> Result<RustupTool> {
+    Rustup::from_env()?.which(tool).await
+}
+
+/// What a toolchain file asks for.
+enum ToolchainSpec {
+    Channel(String),
+    /// A custom toolchain directory, relative to the file.
+    Path(String),
+}
+
+fn parse_toolchain_file(contents: &str) -> std::result::Result<ToolchainSpec, String> {
+    let trimmed = contents.trim();
+    // The legacy format is just the toolchain name.
+    if !trimmed.is_empty() && !trimmed.contains(['\n', '=', '[']) {
+        return Ok(ToolchainSpec::Channel(trimmed.to_owned()));
+    }
+    let table = trimmed.parse::<Table>().map_err(|e| e.to_string())?;
+    let toolchain = table
+        .get("toolchain")
+        .and_then(Value::as_table)
+        .ok_or("missing [toolchain] table")?;
+    let field = |key| toolchain.get(key).and_then(Value::as_str);
+    match (field("channel"), field("path")) {
+        (Some(channel), _) => Ok(ToolchainSpec::Channel(channel.to_owned())),
+        (None, Some(path)) => Ok(ToolchainSpec::Path(path.to_owned())),
+        (None, None) => Err("missing toolchain channel or path".to_owned()),
+    }
+}
+
+fn invalid_config(path: &Path, reason: impl fmt::Display) -> Error {
+    Error::InvalidRustupConfig {
+        path: path.to_path_buf(),
+        reason: reason.to_string(),
+    }
+}
+
+fn home_dir() -> Option<PathBuf> {
+    let var = if cfg!(windows) { "USERPROFILE" } else { "HOME" };
+    env::var_os(var)
+        .filter(|home| !home.is_empty())
+        .map(PathBuf::from)
+}
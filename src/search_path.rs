@@ -0,0 +1,221 @@
+use std::env;
+use std::ffi::{OsStr, OsString};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::finder::{Finder, PathList};
+
+/// The source tag of entries taken from the `PATH` env variable.
+const PATH_SOURCE: &str = "PATH";
+
+/// An ordered list of directories to search, each tagged with where it came from.
+///
+/// Build one instead of joining `PATH`-style strings by hand, then pass it to
+/// [`WhichConfig::search_path`](crate::WhichConfig::search_path). Lookups through
+/// [`WhichConfig::first_sourced`](crate::WhichConfig::first_sourced) report the entry each result
+/// was found in.
+///
+/// # Example
+///
+/// ```no_run
+/// # tokio_test::block_on(async {
+/// use async_which::{SearchPath, WhichConfig};
+///
+/// let search_path = SearchPath::from_env()
+///     .prepend_dir("project", "./node_modules/.bin")
+///     .append_dir("CARGO_HOME", "/home/me/.cargo/bin");
+/// let found = WhichConfig::new()
+///     .binary_name("eslint".into())
+///     .search_path(search_path)
+///     .first_sourced()
+///     .await
+///     .unwrap();
+/// println!("{}", found);
+/// # })
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SearchPath {
+    entries: Vec<SearchEntry>,
+}
+
+/// One directory in a [`SearchPath`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SearchEntry {
+    pub dir: PathBuf,
+    /// Where the directory came from, e.g. `PATH` or the name of another env variable.
+    pub source: String,
+}
+
+impl fmt::Display for SearchEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (from {})", self.dir.display(), self.source)
+    }
+}
+
+impl SearchPath {
+    /// An empty search path.
+    pub fn new() -> SearchPath {
+        SearchPath::default()
+    }
+
+    /// The directories in the `PATH` env variable, tagged `PATH`. Empty if it is unset.
+    pub fn from_env() -> SearchPath {
+        system_search_path().unwrap_or_default()
+    }
+
+    /// Adds `dir` after the existing entries.
+    pub fn append_dir(mut self, source: impl Into<String>, dir: impl Into<PathBuf>) -> Self {
+        self.entries.push(SearchEntry {
+            dir: dir.into(),
+            source: source.into(),
+        });
+        self
+    }
+
+    /// Adds `dir` before the existing entries.
+    pub fn prepend_dir(mut self, source: impl Into<String>, dir: impl Into<PathBuf>) -> Self {
+        self.entries.insert(
+            0,
+            SearchEntry {
+                dir: dir.into(),
+                source: source.into(),
+            },
+        );
+        self
+    }
+
+    /// Adds `dirs`, in order, after the existing entries.
+    pub fn append_dirs<I, P>(mut self, source: impl Into<String>, dirs: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<PathBuf>,
+    {
+        self.entries.extend(tag(source.into(), dirs));
+        self
+    }
+
+    /// Adds `dirs`, in order, before the existing entries.
+    pub fn prepend_dirs<I, P>(mut self, source: impl Into<String>, dirs: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<PathBuf>,
+    {
+        self.entries.splice(0..0, tag(source.into(), dirs));
+        self
+    }
+
+    /// Adds the entries of `other`, keeping their tags, after the existing entries.
+    pub fn append(mut self, other: SearchPath) -> Self {
+        self.entries.extend(other.entries);
+        self
+    }
+
+    /// Adds the entries of `other`, keeping their tags, before the existing entries.
+    pub fn prepend(mut self, other: SearchPath) -> Self {
+        self.entries.splice(0..0, other.entries);
+        self
+    }
+
+    /// Adds the directories of a list separated like the `PATH` env variable after the existing
+    /// entries.
+    pub fn append_path_list(self, source: impl Into<String>, list: impl AsRef<OsStr>) -> Self {
+        self.append_dirs(source, Finder::path_split(list))
+    }
+
+    /// Adds the directories of a list separated like the `PATH` env variable before the existing
+    /// entries.
+    pub fn prepend_path_list(self, source: impl Into<String>, list: impl AsRef<OsStr>) -> Self {
+        self.prepend_dirs(source, Finder::path_split(list))
+    }
+
+    /// Adds the directories listed in the env variable `var` after the existing entries, tagged
+    /// with its name. Does nothing if it is unset.
+    pub fn append_env_var(self, var: &str) -> Self {
+        match env::var_os(var) {
+            Some(list) => self.append_path_list(var, list),
+            None => self,
+        }
+    }
+
+    /// Adds the directories listed in the env variable `var` before the existing entries, tagged
+    /// with its name. Does nothing if it is unset.
+    pub fn prepend_env_var(self, var: &str) -> Self {
+        match env::var_os(var) {
+            Some(list) => self.prepend_path_list(var, list),
+            None => self,
+        }
+    }
+
+    pub fn entries(&self) -> &[SearchEntry] {
+        &self.entries
+    }
+
+    /// The directories in search order, without their tags.
+    pub fn dirs(&self) -> impl Iterator<Item = &Path> {
+        self.entries.iter().map(|entry| entry.dir.as_path())
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Joins the directories into a string separated like the `PATH` env variable, e.g. to pass
+    /// to a child process.
+    pub fn to_path_list(&self) -> Result<OsString, env::JoinPathsError> {
+        env::join_paths(self.dirs())
+    }
+}
+
+impl PathList for SearchPath {
+    fn into_dirs(self) -> Vec<PathBuf> {
+        self.entries.into_iter().map(|entry| entry.dir).collect()
+    }
+}
+
+/// The `PATH` env variable as a search path, or `None` if it is unset.
+pub(crate) fn system_search_path() -> Option<SearchPath> {
+    env::var_os(PATH_SOURCE).map(|list| SearchPath::new().append_path_list(PATH_SOURCE, list))
+}
+
+fn tag<I, P>(source: String, dirs: I) -> Vec<SearchEntry>
+where
+    I: IntoIterator<Item = P>,
+    P: Into<PathBuf>,
+{
+    dirs.into_iter()
+        .map(|dir| SearchEntry {
+            dir: dir.into(),
+            source: source.clone(),
+        })
+        .collect()
+}
+
+/// A path found through a [`SearchPath`], along with the entry it was found in.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SourcedPath {
+    pub path: PathBuf,
+    /// The entry `path` was found in, or `None` if the name had a path separator and was
+    /// resolved against the working directory.
+    pub entry: Option<SearchEntry>,
+}
+
+impl SourcedPath {
+    /// The source tag of [`Self::entry`], if any.
+    pub fn source(&self) -> Option<&str> {
+        self.entry.as_ref().map(|entry| entry.source.as_str())
+    }
+}
+
+impl fmt::Display for SourcedPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.path.display())?;
+        if let Some(source) = self.source() {
+            write!(f, " (from {})", source)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,93 @@
+use std::ffi::OsString;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// The executable a lookup resolves to, and every same-named executable it hides.
+///
+/// Returned by [`which_shadowed`](crate::which_shadowed) and
+/// [`WhichConfig::shadowed`](crate::WhichConfig::shadowed).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Shadowing {
+    /// The name that was looked up.
+    pub name: OsString,
+    /// The search paths that were used, in order.
+    pub search_paths: Vec<PathBuf>,
+    /// What `which` returns for `name`.
+    pub winner: Located,
+    /// Every other executable named `name`, in search order.
+    pub shadowed: Vec<Shadowed>,
+}
+
+impl Shadowing {
+    /// The shadowed executables that are different files from the winner.
+    pub fn distinct(&self) -> impl Iterator<Item = &Shadowed> {
+        self.shadowed.iter().filter(|shadowed| !shadowed.same_file)
+    }
+}
+
+/// An accepted executable and where on the search path it was found.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Located {
+    pub path: PathBuf,
+    /// Index into [`Shadowing::search_paths`] of the entry this path was found in.
+    /// `None` when the path was resolved against the current working directory.
+    pub path_index: Option<usize>,
+}
+
+/// An executable hidden by the [winner](Shadowing::winner) of a lookup.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Shadowed {
+    pub path: PathBuf,
+    /// Index into [`Shadowing::search_paths`] of the entry this path was found in.
+    pub path_index: Option<usize>,
+    /// Whether this is the same file as the winner, e.g. a symlink to it or a directory
+    /// listed twice.
+    pub same_file: bool,
+}
+
+impl fmt::Display for Shadowing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} resolves to ", self.name)?;
+        write_located(f, &self.winner.path, self.winner.path_index)?;
+        writeln!(f)?;
+        for shadowed in &self.shadowed {
+            write!(f, "  shadows ")?;
+            write_located(f, &shadowed.path, shadowed.path_index)?;
+            if shadowed.same_file {
+                write!(f, " (same file)")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+fn write_located(f: &mut fmt::Formatter<'_>, path: &Path, index: Option<usize>) -> fmt::Result {
+    match index {
+        Some(index) => write!(f, "{} [{}]", path.display(), index),
+        None => write!(f, "{} [cwd]", path.display()),
+    }
+}
+
+/// Whether `a` and `b` refer to the same file after following symlinks.
+#[cfg(unix)]
+pub(crate) async fn same_file(a: &Path, b: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    match (tokio::fs::metadata(a).await, tokio::fs::metadata(b).await) {
+        (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+        _ => false,
+    }
+}
+
+/// Whether `a` and `b` refer to the same file after following symlinks.
+#[cfg(not(unix))]
+pub(crate) async fn same_file(a: &Path, b: &Path) -> bool {
+    match (
+        tokio::fs::canonicalize(a).await,
+        tokio::fs::canonicalize(b).await,
+    ) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
@@ -0,0 +1,260 @@
+use std::env;
+use std::ffi::{OsStr, OsString};
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::checker::Checker;
+use crate::error::Error;
+use crate::finder::Finder;
+use crate::format::read_header;
+use crate::verdict::{Rejection, Verdict};
+
+/// How many bytes of a script are read to find its `#!` line, as on Linux.
+const SHEBANG_LEN: usize = 256;
+
+/// How many interpreters may be scripts themselves before execution fails, as on Linux.
+const MAX_DEPTH: usize = 4;
+
+/// The `#!` line of a script.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Shebang {
+    /// The interpreter the operating system runs, e.g. `/usr/bin/env`.
+    pub interpreter: PathBuf,
+    /// The arguments after the interpreter, split on whitespace.
+    pub args: Vec<OsString>,
+}
+
+impl Shebang {
+    /// Parses the `#!` line at the start of `header`, or returns `None` if there is none.
+    pub fn parse(header: &[u8]) -> Option<Shebang> {
+        let line = header.strip_prefix(b"#!")?;
+        let line = match line.iter().position(|&b| b == b'\n') {
+            Some(end) => &line[..end],
+            None => line,
+        };
+        let mut words = line
+            .split(|b| b.is_ascii_whitespace())
+            .filter(|word| !word.is_empty())
+            .map(os_string_from_bytes);
+        let interpreter = PathBuf::from(words.next()?);
+        Some(Shebang {
+            interpreter,
+            args: words.collect(),
+        })
+    }
+
+    /// The program this shebang runs through `env`, e.g. `python3` for
+    /// `#!/usr/bin/env -S python3 -u`, or `None` if the interpreter is not `env`.
+    pub fn env_program(&self) -> Option<OsString> {
+        if self.interpreter.file_name()? != "env" {
+            return None;
+        }
+        let mut args = self.args.iter().map(OsString::as_os_str);
+        while let Some(arg) = args.next() {
+            let bytes = arg.as_encoded_bytes();
+            match bytes {
+                b"-S" | b"--split-string" | b"-i" | b"--ignore-environment" | b"-" => {}
+                b"-u" | b"--unset" | b"-C" | b"--chdir" => {
+                    args.next();
+                }
+                [b'-', b'S', rest @ ..] => return Some(os_string_from_bytes(rest)),
+                [b'-', ..] => {}
+                _ if bytes.contains(&b'=') => {}
+                _ => return Some(arg.to_owned()),
+            }
+        }
+        None
+    }
+}
+
+impl fmt::Display for Shebang {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#!{}", self.interpreter.display())?;
+        for arg in &self.args {
+            write!(f, " {}", arg.to_string_lossy())?;
+        }
+        Ok(())
+    }
+}
+
+/// One interpreter in an [`InterpreterChain`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Interpreter {
+    /// The `#!` line that names this interpreter.
+    pub shebang: Shebang,
+    /// The program that ends up running: the [`env` program](Shebang::env_program) as found
+    /// on the search path, or the interpreter itself. A bare name if `env` found nothing.
+    pub path: PathBuf,
+    /// Whether `path` exists and is executable.
+    pub verdict: Verdict,
+}
+
+/// The interpreters a script goes through before reaching a binary, in order.
+///
+/// Returned by [`interpreter_chain`] and [`interpreter_chain_in`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InterpreterChain {
+    pub script: PathBuf,
+    pub interpreters: Vec<Interpreter>,
+}
+
+impl InterpreterChain {
+    /// The binary that ends up running the script, or `None` if the chain is broken.
+    pub fn program(&self) -> Option<&Path> {
+        match self.rejection() {
+            Some(_) => None,
+            None => self.interpreters.last().map(|last| last.path.as_path()),
+        }
+    }
+
+    /// Why the script cannot run, or `None` if every interpreter was found.
+    pub fn rejection(&self) -> Option<Rejection> {
+        self.interpreters.iter().find_map(|interpreter| {
+            interpreter
+                .verdict
+                .rejection()
+                .map(|reason| Rejection::BrokenInterpreter {
+                    interpreter: interpreter.path.clone(),
+                    reason: Box::new(reason.clone()),
+                })
+        })
+    }
+}
+
+impl fmt::Display for InterpreterChain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.script.display())?;
+        for interpreter in &self.interpreters {
+            write!(f, " -> {}", interpreter.path.display())?;
+            if let Verdict::Rejected(reason) = &interpreter.verdict {
+                write!(f, " ({})", reason)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reads the `#!` line of the file at `path`, or returns `None` if it is not a script.
+pub async fn read_shebang<P: AsRef<Path>>(path: P) -> io::Result<Option<Shebang>> {
+    let mut header = [0; SHEBANG_LEN];
+    let len = read_header(path.as_ref(), &mut header).await?;
+    Ok(Shebang::parse(&header[..len]))
+}
+
+/// Follows the `#!` lines of `script` to the binary that runs it, looking up `env` programs
+/// in `PATH`. Returns `None` if `script` is not a script.
+///
+/// # Example
+///
+/// ```no_run
+/// # tokio_test::block_on(async {
+/// let script = async_which::which("pip3").await.unwrap();
+/// if let Some(chain) = async_which::interpreter_chain(&script).await.unwrap() {
+///     println!("{}", chain);
+/// }
+/// # })
+/// ```
+pub async fn interpreter_chain<P: AsRef<Path>>(script: P) -> io::Result<Option<InterpreterChain>> {
+    interpreter_chain_in(script, env::var_os("PATH")).await
+}
+
+/// Follows the `#!` lines of `script` to the binary that runs it, looking up `env` programs
+/// in `paths`. Returns `None` if `script` is not a script.
+pub async fn interpreter_chain_in<P, U>(
+    script: P,
+    paths: Option<U>,
+) -> io::Result<Option<InterpreterChain>>
+where
+    P: AsRef<Path>,
+    U: AsRef<OsStr>,
+{
+    let script = script.as_ref().to_path_buf();
+    let paths = paths.map(|p| p.as_ref().to_owned());
+
+    let mut shebang = match read_shebang(&script).await? {
+        Some(shebang) => shebang,
+        None => return Ok(None),
+    };
+    let mut interpreters: Vec<Interpreter> = Vec::new();
+    loop {
+        let (path, verdict) = resolve(&shebang, paths.as_deref()).await;
+        let next = match verdict {
+            Verdict::Accepted => read_shebang(&path).await.ok().flatten(),
+            Verdict::Rejected(_) => None,
+        };
+        interpreters.push(Interpreter {
+            shebang,
+            path,
+            verdict,
+        });
+        shebang = match next {
+            Some(next) => next,
+            None => break,
+        };
+        if interpreters.len() == MAX_DEPTH {
+            if let Some(last) = interpreters.last_mut() {
+                last.verdict =
+                    Verdict::Rejected(Rejection::Other("nests too many interpreters".to_owned()));
+            }
+            break;
+        }
+    }
+
+    Ok(Some(InterpreterChain {
+        script,
+        interpreters,
+    }))
+}
+
+/// Finds the program `shebang` runs and checks that it can be executed.
+async fn resolve(shebang: &Shebang, paths: Option<&OsStr>) -> (PathBuf, Verdict) {
+    let binary_checker = crate::build_binary_checker();
+    let verdict = binary_checker.check(&shebang.interpreter).await;
+    let program = match (&verdict, shebang.env_program()) {
+        (Verdict::Accepted, Some(program)) => program,
+        _ => return (shebang.interpreter.clone(), verdict),
+    };
+
+    if Path::new(&program).components().count() > 1 {
+        let verdict = binary_checker.check(Path::new(&program)).await;
+        return (PathBuf::from(program), verdict);
+    }
+    let found = Finder::new()
+        .find_first(&program, paths, Option::<&Path>::None, binary_checker)
+        .await;
+    match found {
+        Ok(path) => (path, Verdict::Accepted),
+        Err(Error::NotExecutable {
+            candidate,
+            mode,
+            source,
+            ..
+        }) => (
+            candidate,
+            Verdict::Rejected(Rejection::NotExecutable {
+                mode,
+                errno: source.raw_os_error(),
+            }),
+        ),
+        Err(Error::Rejected {
+            candidate, reason, ..
+        }) => (candidate, Verdict::Rejected(reason)),
+        Err(_) => (
+            PathBuf::from(program),
+            Verdict::Rejected(Rejection::Missing),
+        ),
+    }
+}
+
+#[cfg(unix)]
+fn os_string_from_bytes(bytes: &[u8]) -> OsString {
+    use std::os::unix::ffi::OsStrExt;
+
+    OsStr::from_bytes(bytes).to_owned()
+}
+
+#[cfg(not(unix))]
+fn os_string_from_bytes(bytes: &[u8]) -> OsString {
+    String::from_utf8_lossy(bytes).into_owned().into()
+}
@@ -0,0 +1,204 @@
+use std::fmt;
+use std::io;
+use std::iter;
+use std::path::{Path, PathBuf};
+
+use crate::checker::Checker;
+use crate::verdict::{Rejection, Verdict};
+
+/// How many links are followed before giving up, as `MAXSYMLINKS` on Linux.
+const MAX_HOPS: usize = 40;
+
+/// The symbolic links followed from a path to the file it refers to.
+///
+/// Only links in the last component are followed, so the chain reads the way tools such as
+/// `update-alternatives` set them up.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SymlinkChain {
+    /// The path the chain starts from.
+    pub path: PathBuf,
+    /// Each link followed, in order.
+    pub hops: Vec<SymlinkHop>,
+    /// Whether resolution stopped because it reached a link it had already followed, or
+    /// followed too many links.
+    pub looped: bool,
+}
+
+/// One link in a [`SymlinkChain`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SymlinkHop {
+    /// The link itself.
+    pub link: PathBuf,
+    /// The target stored in the link, which may be relative.
+    pub target: PathBuf,
+    /// `target` resolved against the directory holding the link.
+    pub resolved: PathBuf,
+}
+
+impl SymlinkChain {
+    /// Where the chain ends: the last resolved target, or `path` if it is not a link.
+    pub fn target(&self) -> &Path {
+        self.hops
+            .last()
+            .map_or(self.path.as_path(), |hop| hop.resolved.as_path())
+    }
+
+    pub fn is_symlink(&self) -> bool {
+        !self.hops.is_empty()
+    }
+}
+
+impl fmt::Display for SymlinkChain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.path.display())?;
+        for hop in &self.hops {
+            write!(f, " -> {}", hop.target.display())?;
+        }
+        if self.looped {
+            write!(f, " (loop)")?;
+        }
+        Ok(())
+    }
+}
+
+/// Follows the symbolic links at `path`, stopping at the first path that is not a link or
+/// does not exist.
+///
+/// # Example
+///
+/// ```no_run
+/// # tokio_test::block_on(async {
+/// let python = async_which::which("python3").await.unwrap();
+/// // e.g. /usr/bin/python3 -> /etc/alternatives/python3 -> /usr/bin/python3.11
+/// println!("{}", async_which::symlink_chain(&python).await.unwrap());
+/// # })
+/// ```
+pub async fn symlink_chain<P: AsRef<Path>>(path: P) -> io::Result<SymlinkChain> {
+    let path = path.as_ref().to_path_buf();
+    let mut hops: Vec<SymlinkHop> = Vec::new();
+    let mut current = path.clone();
+    loop {
+        match tokio::fs::symlink_metadata(&current).await {
+            Ok(metadata) if metadata.file_type().is_symlink() => {}
+            Ok(_) => break,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => break,
+            Err(e) => return Err(e),
+        }
+        if hops.len() == MAX_HOPS || hops.iter().any(|hop| hop.link == current) {
+            return Ok(SymlinkChain {
+                path,
+                hops,
+                looped: true,
+            });
+        }
+
+        let target = tokio::fs::read_link(&current).await?;
+        let resolved = match current.parent() {
+            Some(parent) if target.is_relative() => parent.join(&target),
+            _ => target.clone(),
+        };
+        hops.push(SymlinkHop {
+            link: current,
+            target,
+            resolved: resolved.clone(),
+        });
+        current = resolved;
+    }
+
+    Ok(SymlinkChain {
+        path,
+        hops,
+        looped: false,
+    })
+}
+
+/// What to do with candidates that are symbolic links.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum SymlinkPolicy {
+    /// Follow links, rejecting only loops.
+    #[default]
+    Follow,
+    /// Reject every candidate that is a link.
+    Reject,
+    /// Follow links, rejecting loops and candidates that resolve outside this directory, whether
+    /// through their own links or through a linked directory above them. Hops that cannot be
+    /// resolved are rejected too.
+    WithinRoot(PathBuf),
+}
+
+/// Applies a [`SymlinkPolicy`] to candidates. Candidates that are not links are accepted, unless
+/// they are outside the root of [`SymlinkPolicy::WithinRoot`].
+#[derive(Clone, Debug, Default)]
+pub struct SymlinkChecker {
+    policy: SymlinkPolicy,
+}
+
+impl SymlinkChecker {
+    pub fn new(policy: SymlinkPolicy) -> SymlinkChecker {
+        SymlinkChecker { policy }
+    }
+}
+
+#[async_trait::async_trait]
+impl Checker for SymlinkChecker {
+    async fn is_valid(&self, path: &Path) -> bool {
+        self.check(path).await.is_accepted()
+    }
+
+    async fn check(&self, path: &Path) -> Verdict {
+        let chain = match symlink_chain(path).await {
+            Ok(chain) => chain,
+            Err(e) => return Verdict::Rejected(Rejection::Io(e.into())),
+        };
+        if let (SymlinkPolicy::Reject, Some(first)) = (&self.policy, chain.hops.first()) {
+            return Verdict::Rejected(Rejection::Symlink {
+                target: first.target.clone(),
+            });
+        }
+        if chain.looped {
+            return Verdict::Rejected(Rejection::SymlinkLoop);
+        }
+
+        if let SymlinkPolicy::WithinRoot(root) = &self.policy {
+            let root = tokio::fs::canonicalize(root)
+                .await
+                .unwrap_or_else(|_| root.clone());
+            // The candidate itself is checked too, in case a directory above it is a link.
+            let paths = iter::once(&chain.path).chain(chain.hops.iter().map(|hop| &hop.resolved));
+            for path in paths {
+                let resolved = match canonicalize_parent(path).await {
+                    Ok(resolved) => resolved,
+                    Err(e) => return Verdict::Rejected(Rejection::Io(e.into())),
+                };
+                if !resolved.starts_with(&root) {
+                    return Verdict::Rejected(Rejection::EscapesRoot {
+                        target: resolved,
+                        root,
+                    });
+                }
+            }
+        }
+        Verdict::Accepted
+    }
+
+    fn name(&self) -> &str {
+        "symlink"
+    }
+}
+
+/// Canonicalizes the directory holding `path`, leaving its last component as is: canonicalizing
+/// `path` itself would follow the rest of a symlink chain and skip the hops in between.
+async fn canonicalize_parent(path: &Path) -> io::Result<PathBuf> {
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(file_name)) => {
+            let parent = if parent.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                parent
+            };
+            let parent = tokio::fs::canonicalize(parent).await?;
+            Ok(parent.join(file_name))
+        }
+        _ => tokio::fs::canonicalize(path).await,
+    }
+}
@@ -0,0 +1,169 @@
+use std::env;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+
+use crate::error::{Error, Result};
+use crate::finder::Finder;
+use crate::search_path::SearchPath;
+
+/// Tools shipped inside a Rust sysroot, such as `rust-lld`, the `gcc-ld` linker wrappers and
+/// the binaries of the `llvm-tools` component.
+///
+/// These live in `lib/rustlib/<host>/bin` and `lib/rustlib/<host>/bin/gcc-ld`, which are never
+/// on `PATH`.
+///
+/// # Example
+///
+/// ```no_run
+/// # tokio_test::block_on(async {
+/// use async_which::Sysroot;
+///
+/// let rustc = async_which::which("rustc").await.unwrap();
+/// let sysroot = Sysroot::from_rustc(&rustc).await.unwrap();
+/// let lld = sysroot.which("rust-lld").await.unwrap();
+/// # })
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Sysroot {
+    root: PathBuf,
+    host: Option<String>,
+}
+
+impl Sysroot {
+    /// The sysroot at `root`, e.g. a rustup toolchain directory.
+    pub fn new(root: impl Into<PathBuf>) -> Sysroot {
+        Sysroot {
+            root: root.into(),
+            host: None,
+        }
+    }
+
+    /// The sysroot a `rustc` binary belongs to, two levels above it once symbolic links are
+    /// resolved.
+    ///
+    /// Pass the binary itself rather than a rustup proxy, which is not inside any sysroot. With
+    /// the `rustup` feature, `Rustup::which` finds it.
+    pub async fn from_rustc<P: AsRef<Path>>(rustc: P) -> Result<Sysroot> {
+        let rustc = rustc.as_ref();
+        let canonical =
+            tokio::fs::canonicalize(rustc)
+                .await
+                .map_err(|source| Error::CannotCanonicalize {
+                    path: rustc.to_path_buf(),
+                    source,
+                })?;
+        let root = canonical.ancestors().nth(2).unwrap_or(&canonical);
+        Ok(Sysroot::new(root))
+    }
+
+    /// Uses the tools for the target `triple` instead of finding the host.
+    pub fn host(mut self, triple: impl Into<String>) -> Self {
+        self.host = Some(triple.into());
+        self
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// The target whose tools run on this machine: the one set with [`Self::host`], else the
+    /// first of these that ships binaries in `lib/rustlib`:
+    ///
+    /// - the host of the `rustc` component recorded in `lib/rustlib/components`,
+    /// - the host rustup names the toolchain directory after, e.g. `stable-<host>`,
+    /// - the target of the running platform.
+    ///
+    /// Failing those, the target in `lib/rustlib` that ships binaries, preferring the running
+    /// architecture if several do.
+    pub async fn host_triple(&self) -> Option<String> {
+        if let Some(host) = &self.host {
+            return Some(host.clone());
+        }
+
+        let mut entries = tokio::fs::read_dir(self.rustlib()).await.ok()?;
+        let mut hosts = Vec::new();
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let is_dir = tokio::fs::metadata(entry.path().join("bin"))
+                .await
+                .is_ok_and(|metadata| metadata.is_dir());
+            if let (true, Ok(name)) = (is_dir, entry.file_name().into_string()) {
+                hosts.push(name);
+            }
+        }
+        hosts.sort();
+
+        let components = tokio::fs::read_to_string(self.rustlib().join("components"))
+            .await
+            .unwrap_or_default();
+        let recorded = components
+            .lines()
+            .filter_map(|component| component.trim().strip_prefix("rustc-"));
+        let toolchain_name = self.root.file_name().and_then(|name| name.to_str());
+        let named = hosts.iter().map(String::as_str).filter(|host| {
+            toolchain_name.is_some_and(|name| name.ends_with(&format!("-{}", host)))
+        });
+        let platform = running_host_triple();
+        let known = recorded.chain(named).chain(platform.as_deref());
+        for host in known {
+            if hosts.iter().any(|installed| installed == host) {
+                return Some(host.to_owned());
+            }
+        }
+
+        let running = format!("{}-", env::consts::ARCH);
+        let preferred = hosts.iter().position(|host| host.starts_with(&running));
+        match preferred {
+            Some(index) => Some(hosts.swap_remove(index)),
+            None => hosts.into_iter().next(),
+        }
+    }
+
+    /// The directories holding the sysroot tools, tagged `rustlib` and `gcc-ld`. Empty if no
+    /// host target was found.
+    pub async fn search_path(&self) -> SearchPath {
+        match self.host_triple().await {
+            Some(host) => {
+                let bin = self.rustlib().join(host).join("bin");
+                SearchPath::new()
+                    .append_dir("rustlib", &bin)
+                    .append_dir("gcc-ld", bin.join("gcc-ld"))
+            }
+            None => SearchPath::new(),
+        }
+    }
+
+    /// Finds `tool` among the sysroot tools, with the same checks as [`which`](crate::which).
+    pub async fn which<T: AsRef<OsStr>>(&self, tool: T) -> Result<PathBuf> {
+        Finder::new()
+            .find_first(
+                tool,
+                Some(self.search_path().await),
+                Option::<&Path>::None,
+                crate::build_binary_checker(),
+            )
+            .await
+    }
+
+    fn rustlib(&self) -> PathBuf {
+        self.root.join("lib").join("rustlib")
+    }
+}
+
+/// A best guess at the target triple of the running platform, as rustup names host toolchains.
+pub(crate) fn running_host_triple() -> Option<String> {
+    let os = match env::consts::OS {
+        "linux" if cfg!(target_env = "musl") => "unknown-linux-musl",
+        "linux" => "unknown-linux-gnu",
+        "macos" => "apple-darwin",
+        "windows" if cfg!(target_env = "gnu") => "pc-windows-gnu",
+        "windows" => "pc-windows-msvc",
+        "freebsd" => "unknown-freebsd",
+        _ => return None,
+    };
+    // Rust calls 32-bit x86 `x86`, but rustup names its toolchains after the `i686` targets.
+    let arch = match env::consts::ARCH {
+        "x86" => "i686",
+        arch => arch,
+    };
+    Some(format!("{}-{}", arch, os))
+}
@@ -0,0 +1,40 @@
+//! Thin wrappers around `tracing` that compile to nothing unless the `tracing` feature is enabled.
+
+/// Emits a `tracing` event, e.g. `trace_event!(DEBUG, path = ?p, "message")`.
+macro_rules! trace_event {
+    ($level:ident, $($arg:tt)+) => {
+        #[cfg(feature = "tracing")]
+        {
+            tracing::event!(tracing::Level::$level, $($arg)+);
+        }
+    };
+}
+
+/// Runs `$fut` inside a `DEBUG` span built from the remaining arguments.
+macro_rules! instrumented {
+    ($fut:expr, $($span:tt)+) => {{
+        #[cfg(feature = "tracing")]
+        let fut = tracing::Instrument::instrument($fut, tracing::debug_span!($($span)+));
+        #[cfg(not(feature = "tracing"))]
+        let fut = $fut;
+        fut
+    }};
+}
+
+/// Polls `$stream` inside a `DEBUG` span built from the remaining arguments, so one span covers
+/// the whole lookup.
+macro_rules! instrumented_stream {
+    ($stream:expr, $($span:tt)+) => {{
+        #[cfg(feature = "tracing")]
+        let stream = {
+            let span = tracing::debug_span!($($span)+);
+            let mut stream = Box::pin($stream);
+            futures::stream::poll_fn(move |cx| {
+                span.in_scope(|| futures::Stream::poll_next(stream.as_mut(), cx))
+            })
+        };
+        #[cfg(not(feature = "tracing"))]
+        let stream = $stream;
+        stream
+    }};
+}
@@ -0,0 +1,237 @@
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+#[cfg(feature = "checksum")]
+use crate::checksum::Sha256Digest;
+use crate::format::{BinaryKind, ElfArch};
+#[cfg(feature = "version")]
+use crate::version::{Version, VersionReq};
+
+/// The outcome of checking a single candidate path.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Verdict {
+    Accepted,
+    Rejected(Rejection),
+}
+
+impl Verdict {
+    pub fn is_accepted(&self) -> bool {
+        matches!(self, Verdict::Accepted)
+    }
+
+    /// Why the candidate was rejected, if it was.
+    pub fn rejection(&self) -> Option<&Rejection> {
+        match self {
+            Verdict::Accepted => None,
+            Verdict::Rejected(reason) => Some(reason),
+        }
+    }
+}
+
+impl From<bool> for Verdict {
+    fn from(valid: bool) -> Self {
+        if valid {
+            Verdict::Accepted
+        } else {
+            Verdict::Rejected(Rejection::Unspecified)
+        }
+    }
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Verdict::Accepted => write!(f, "accepted"),
+            Verdict::Rejected(reason) => write!(f, "{}", reason),
+        }
+    }
+}
+
+/// Why a checker rejected a candidate path.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Rejection {
+    /// Nothing exists at the candidate path.
+    Missing,
+    /// The candidate is a directory.
+    Directory,
+    /// The candidate exists but is neither a regular file nor a symlink to one.
+    NotAFile,
+    /// The candidate is a symbolic link whose target does not exist.
+    BrokenSymlink,
+    /// The candidate is a file but cannot be executed.
+    ///
+    /// `mode` holds the permission bits on Unix, `errno` the OS error reported by the check.
+    NotExecutable {
+        mode: Option<u32>,
+        errno: Option<i32>,
+    },
+    /// Any user can modify `path`, the candidate or one of its directories.
+    WorldWritable { path: PathBuf, mode: u32 },
+    /// `path`, the candidate or one of its directories, is owned by an untrusted user.
+    UntrustedOwner { path: PathBuf, uid: u32 },
+    /// The candidate's header shows it is not an acceptable executable, e.g. it is empty.
+    UnexpectedFormat(BinaryKind),
+    /// The candidate is an ELF binary for `found` rather than `expected`.
+    WrongArchitecture { expected: ElfArch, found: ElfArch },
+    /// The candidate is a script whose `interpreter`, or the program it runs through `env`,
+    /// was rejected for `reason`.
+    BrokenInterpreter {
+        interpreter: PathBuf,
+        reason: Box<Rejection>,
+    },
+    /// The candidate reported version `found`, which does not satisfy `required`.
+    #[cfg(feature = "version")]
+    VersionMismatch {
+        found: Version,
+        required: VersionReq,
+    },
+    /// The candidate's version could not be determined, for the given reason.
+    #[cfg(feature = "version")]
+    UnknownVersion(String),
+    /// The candidate's SHA-256 digest is not in the allowlist.
+    #[cfg(feature = "checksum")]
+    UnknownChecksum(Sha256Digest),
+    /// The candidate is a symbolic link to `target`, and links are not allowed.
+    Symlink { target: PathBuf },
+    /// Following the candidate's symbolic links leads back to one of them.
+    SymlinkLoop,
+    /// The candidate's symbolic links lead to `target`, outside of `root`.
+    EscapesRoot { target: PathBuf, root: PathBuf },
+    /// The candidate path cannot be passed to the operating system, e.g. it contains a NUL.
+    InvalidName,
+    /// Inspecting the candidate failed.
+    Io(SharedIoError),
+    /// The checker did not say why, e.g. it only implements `is_valid`.
+    Unspecified,
+    /// A free-form reason, for checkers outside this crate.
+    Other(String),
+}
+
+impl Rejection {
+    /// The OS error behind this rejection, if there is one.
+    pub fn io_error(&self) -> Option<io::Error> {
+        match self {
+            Rejection::NotExecutable { errno, .. } => Some(
+                errno
+                    .map(io::Error::from_raw_os_error)
+                    .unwrap_or_else(|| io::ErrorKind::PermissionDenied.into()),
+            ),
+            Rejection::Missing | Rejection::BrokenSymlink => Some(io::ErrorKind::NotFound.into()),
+            Rejection::Io(error) => Some(match error.raw_os_error() {
+                Some(errno) => io::Error::from_raw_os_error(errno),
+                None => error.kind().into(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Rejection::Missing => write!(f, "does not exist"),
+            Rejection::Directory => write!(f, "is a directory"),
+            Rejection::NotAFile => write!(f, "is not a regular file"),
+            Rejection::BrokenSymlink => write!(f, "is a broken symbolic link"),
+            Rejection::NotExecutable {
+                mode: Some(mode), ..
+            } => write!(f, "is not executable (mode {:04o})", mode),
+            Rejection::NotExecutable { mode: None, .. } => write!(f, "is not executable"),
+            Rejection::WorldWritable { path, mode } => write!(
+                f,
+                "is insecure: {} is world-writable (mode {:04o})",
+                path.display(),
+                mode
+            ),
+            Rejection::UntrustedOwner { path, uid } => {
+                write!(f, "is insecure: {} is owned by uid {}", path.display(), uid)
+            }
+            Rejection::UnexpectedFormat(kind) => {
+                write!(f, "is not an acceptable executable ({})", kind)
+            }
+            Rejection::WrongArchitecture { expected, found } => {
+                write!(f, "is built for {} instead of {}", found, expected)
+            }
+            Rejection::BrokenInterpreter {
+                interpreter,
+                reason,
+            } => write!(
+                f,
+                "is a script whose interpreter {} {}",
+                interpreter.display(),
+                reason
+            ),
+            #[cfg(feature = "version")]
+            Rejection::VersionMismatch { found, required } => {
+                write!(
+                    f,
+                    "has version {}, which does not satisfy {}",
+                    found, required
+                )
+            }
+            #[cfg(feature = "version")]
+            Rejection::UnknownVersion(reason) => write!(f, "has an unknown version: {}", reason),
+            #[cfg(feature = "checksum")]
+            Rejection::UnknownChecksum(digest) => {
+                write!(f, "has SHA-256 digest {}, which is not allowed", digest)
+            }
+            Rejection::Symlink { target } => {
+                write!(f, "is a symbolic link to {}", target.display())
+            }
+            Rejection::SymlinkLoop => write!(f, "is a symbolic link loop"),
+            Rejection::EscapesRoot { target, root } => write!(
+                f,
+                "links to {}, outside of {}",
+                target.display(),
+                root.display()
+            ),
+            Rejection::InvalidName => write!(f, "is not a valid path"),
+            Rejection::Io(error) => write!(f, "cannot be inspected ({})", error),
+            Rejection::Unspecified => write!(f, "was rejected"),
+            Rejection::Other(reason) => write!(f, "{}", reason),
+        }
+    }
+}
+
+/// An I/O error that can be cloned along with the [`Rejection`] holding it.
+///
+/// Two are equal if they have the same kind and OS error code.
+#[derive(Clone, Debug)]
+pub struct SharedIoError(Arc<io::Error>);
+
+impl SharedIoError {
+    pub fn get_ref(&self) -> &io::Error {
+        &self.0
+    }
+
+    pub fn kind(&self) -> io::ErrorKind {
+        self.0.kind()
+    }
+
+    pub fn raw_os_error(&self) -> Option<i32> {
+        self.0.raw_os_error()
+    }
+}
+
+impl From<io::Error> for SharedIoError {
+    fn from(error: io::Error) -> Self {
+        SharedIoError(Arc::new(error))
+    }
+}
+
+impl PartialEq for SharedIoError {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind() == other.kind() && self.raw_os_error() == other.raw_os_error()
+    }
+}
+
+impl Eq for SharedIoError {}
+
+impl fmt::Display for SharedIoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
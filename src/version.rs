@@ -0,0 +1,386 @@
+use std::cmp::Ordering;
+use std::ffi::OsString;
+use std::fmt;
+use std::path::Path;
+use std::process::Stdio;
+use std::str::FromStr;
+use std::time::Duration;
+
+use regex::Regex;
+
+use crate::checker::Checker;
+use crate::verdict::{Rejection, Verdict};
+
+/// A `major.minor.patch` version, parsed leniently from tool output.
+///
+/// Missing components are zero and anything after the digits of a component is ignored, so
+/// `v3.20`, `3.20.0-rc1` and `3.20.0` all parse as 3.20.0.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Version {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+}
+
+impl Version {
+    pub fn new(major: u64, minor: u64, patch: u64) -> Version {
+        Version {
+            major,
+            minor,
+            patch,
+        }
+    }
+}
+
+impl FromStr for Version {
+    type Err = VersionParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let partial = Partial::parse(s.trim().trim_start_matches('v'))
+            .filter(|partial| partial.major.is_some())
+            .ok_or_else(|| VersionParseError::new(s))?;
+        Ok(partial.lower_bound())
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// A set of comparators a [`Version`] must all satisfy, e.g. `>=3.20, <4`.
+///
+/// Supports `=`, `>`, `>=`, `<`, `<=`, `~`, `^` and `*`, and wildcard components such as
+/// `3.x`. A bare version means `^`, as in Cargo.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct VersionReq {
+    comparators: Vec<Comparator>,
+}
+
+impl VersionReq {
+    /// Parses a requirement such as `>=3.20, <4`.
+    pub fn parse(s: &str) -> Result<VersionReq, VersionParseError> {
+        s.parse()
+    }
+
+    pub fn matches(&self, version: &Version) -> bool {
+        self.comparators
+            .iter()
+            .all(|comparator| comparator.matches(version))
+    }
+}
+
+impl FromStr for VersionReq {
+    type Err = VersionParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let comparators = s
+            .split(',')
+            .map(|part| Comparator::parse(part.trim()).ok_or_else(|| VersionParseError::new(s)))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(VersionReq { comparators })
+    }
+}
+
+impl fmt::Display for VersionReq {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, comparator) in self.comparators.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", comparator)?;
+        }
+        Ok(())
+    }
+}
+
+/// A version or version requirement that could not be parsed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VersionParseError {
+    input: String,
+}
+
+impl VersionParseError {
+    fn new(input: &str) -> VersionParseError {
+        VersionParseError {
+            input: input.to_owned(),
+        }
+    }
+}
+
+impl fmt::Display for VersionParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid version or requirement {:?}", self.input)
+    }
+}
+
+impl std::error::Error for VersionParseError {}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Op {
+    Exact,
+    Greater,
+    GreaterEq,
+    Less,
+    LessEq,
+    Tilde,
+    Caret,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct Comparator {
+    op: Op,
+    version: Partial,
+}
+
+/// A version whose trailing components may be left out or wildcards.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct Partial {
+    major: Option<u64>,
+    minor: Option<u64>,
+    patch: Option<u64>,
+}
+
+impl Partial {
+    fn parse(s: &str) -> Option<Partial> {
+        let mut components = [None; 3];
+        for (i, component) in s.split('.').enumerate() {
+            if i == components.len() {
+                break;
+            }
+            if matches!(component, "*" | "x" | "X") {
+                break;
+            }
+            let digits = component
+                .find(|c: char| !c.is_ascii_digit())
+                .map_or(component, |end| &component[..end]);
+            components[i] = Some(digits.parse().ok()?);
+            if digits.len() < component.len() {
+                break;
+            }
+        }
+        let [major, minor, patch] = components;
+        Some(Partial {
+            major,
+            minor,
+            patch,
+        })
+    }
+
+    fn lower_bound(&self) -> Version {
+        Version::new(
+            self.major.unwrap_or(0),
+            self.minor.unwrap_or(0),
+            self.patch.unwrap_or(0),
+        )
+    }
+
+    /// Compares only the components this version specifies.
+    fn cmp_prefix(&self, version: &Version) -> Ordering {
+        let pairs = [
+            (self.major, version.major),
+            (self.minor, version.minor),
+            (self.patch, version.patch),
+        ];
+        pairs
+            .iter()
+            .map_while(|(mine, theirs)| mine.map(|mine| theirs.cmp(&mine)))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+    }
+}
+
+impl Comparator {
+    fn parse(s: &str) -> Option<Comparator> {
+        let (op, rest) = [
+            (">=", Op::GreaterEq),
+            ("<=", Op::LessEq),
+            (">", Op::Greater),
+            ("<", Op::Less),
+            ("=", Op::Exact),
+            ("~", Op::Tilde),
+            ("^", Op::Caret),
+        ]
+        .iter()
+        .find_map(|(prefix, op)| s.strip_prefix(prefix).map(|rest| (*op, rest)))
+        .unwrap_or((Op::Caret, s));
+        let rest = rest.trim();
+        let version = Partial::parse(rest.trim_start_matches('v'))?;
+        if version.major.is_none() && !matches!(rest, "*" | "x" | "X") {
+            return None;
+        }
+        Some(Comparator { op, version })
+    }
+
+    fn matches(&self, version: &Version) -> bool {
+        let partial = &self.version;
+        let ordering = partial.cmp_prefix(version);
+        match self.op {
+            Op::Exact => ordering.is_eq(),
+            Op::Greater => ordering.is_gt(),
+            Op::GreaterEq => *version >= partial.lower_bound(),
+            Op::Less => *version < partial.lower_bound(),
+            Op::LessEq => ordering.is_le(),
+            Op::Tilde => {
+                let prefix = Partial {
+                    patch: None,
+                    ..*partial
+                };
+                *version >= partial.lower_bound() && prefix.cmp_prefix(version).is_eq()
+            }
+            Op::Caret => {
+                let prefix = match (partial.major, partial.minor, partial.patch) {
+                    (Some(0), Some(0), Some(_)) => *partial,
+                    (Some(0), Some(_), _) => Partial {
+                        patch: None,
+                        ..*partial
+                    },
+                    _ => Partial {
+                        minor: None,
+                        patch: None,
+                        ..*partial
+                    },
+                };
+                *version >= partial.lower_bound() && prefix.cmp_prefix(version).is_eq()
+            }
+        }
+    }
+}
+
+impl fmt::Display for Comparator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let op = match self.op {
+            Op::Exact => "=",
+            Op::Greater => ">",
+            Op::GreaterEq => ">=",
+            Op::Less => "<",
+            Op::LessEq => "<=",
+            Op::Tilde => "~",
+            Op::Caret => "^",
+        };
+        let partial = &self.version;
+        match (partial.major, partial.minor, partial.patch) {
+            (None, ..) => write!(f, "*"),
+            (Some(major), None, _) => write!(f, "{}{}", op, major),
+            (Some(major), Some(minor), None) => write!(f, "{}{}.{}", op, major, minor),
+            (Some(major), Some(minor), Some(patch)) => {
+                write!(f, "{}{}.{}.{}", op, major, minor, patch)
+            }
+        }
+    }
+}
+
+/// Accepts binaries whose reported version satisfies a [`VersionReq`].
+///
+/// Each candidate is **run** with [`Self::arg`] (`--version` by default) and its standard
+/// output, then standard error, is searched with [`Self::pattern`]. Only use this on search
+/// paths whose binaries are trusted to run.
+#[derive(Clone, Debug)]
+pub struct VersionChecker {
+    req: VersionReq,
+    args: Vec<OsString>,
+    timeout: Duration,
+    pattern: Regex,
+}
+
+impl VersionChecker {
+    pub fn new(req: VersionReq) -> VersionChecker {
+        VersionChecker {
+            req,
+            args: vec!["--version".into()],
+            timeout: Duration::from_secs(5),
+            pattern: Regex::new(r"\d+(?:\.\d+)+").expect("valid version pattern"),
+        }
+    }
+
+    /// Runs candidates with `arg` instead of `--version`, e.g. `-version` for `java`.
+    pub fn arg(mut self, arg: impl Into<OsString>) -> Self {
+        self.args = vec![arg.into()];
+        self
+    }
+
+    /// Runs candidates with `args` instead of `--version`.
+    pub fn args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<OsString>,
+    {
+        self.args = args.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Kills candidates that run longer than `timeout`, and rejects them. Five seconds by
+    /// default.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Extracts the version with `pattern` instead of the first dotted number. The capture
+    /// group named `version` is used if there is one, then the first group, then the whole
+    /// match.
+    pub fn pattern(mut self, pattern: Regex) -> Self {
+        self.pattern = pattern;
+        self
+    }
+
+    fn extract(&self, output: &str) -> Option<Version> {
+        self.pattern.captures_iter(output).find_map(|captures| {
+            captures
+                .name("version")
+                .or_else(|| captures.get(1))
+                .or_else(|| captures.get(0))
+                .and_then(|version| version.as_str().parse().ok())
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl Checker for VersionChecker {
+    async fn is_valid(&self, path: &Path) -> bool {
+        self.check(path).await.is_accepted()
+    }
+
+    async fn check(&self, path: &Path) -> Verdict {
+        let output = tokio::process::Command::new(path)
+            .args(&self.args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .output();
+        let output = match tokio::time::timeout(self.timeout, output).await {
+            Ok(Ok(output)) => output,
+            Ok(Err(e)) => return Verdict::Rejected(Rejection::Io(e.into())),
+            Err(_) => {
+                return Verdict::Rejected(Rejection::UnknownVersion(format!(
+                    "timed out after {:?}",
+                    self.timeout
+                )))
+            }
+        };
+
+        let found = [&output.stdout, &output.stderr]
+            .iter()
+            .find_map(|stream| self.extract(&String::from_utf8_lossy(stream)));
+        match found {
+            Some(found) if self.req.matches(&found) => Verdict::Accepted,
+            Some(found) => Verdict::Rejected(Rejection::VersionMismatch {
+                found,
+                required: self.req.clone(),
+            }),
+            None => Verdict::Rejected(Rejection::UnknownVersion(
+                "printed no recognizable version".to_owned(),
+            )),
+        }
+    }
+
+    fn name(&self) -> &str {
+        "version"
+    }
+
+    fn cost(&self) -> u32 {
+        100
+    }
+}
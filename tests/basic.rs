@@ -38,6 +38,7 @@ async fn mk_bin(dir: &Path, path: &str, extension: &str) -> io::Result<PathBuf>
     tokio::fs::OpenOptions::new()
         .write(true)
         .create(true)
+        .truncate(true)
         .mode(mode)
         .open(&bin)
         .await
@@ -159,7 +160,7 @@ async fn _which<T: AsRef<OsStr>>(
 fn _which_all<'a, T: AsRef<OsStr> + 'a>(
     f: &'a TestFixture,
     path: T,
-) -> impl Stream<Item = async_which::Result<async_which::CanonicalPath>> + '_ {
+) -> impl Stream<Item = async_which::Result<async_which::CanonicalPath>> + 'a {
     async_which::CanonicalPath::all_in(path, Some(f.paths.clone()), f.tempdir.path())
 }
 
@@ -515,3 +516,1726 @@ async fn test_failure() {
 
     let _ = run.await;
 }
+
+#[tokio::test]
+async fn test_which_not_found_error_context() {
+    let f = TestFixture::new().await;
+    match _which(&f, "a").await {
+        Err(async_which::Error::CannotFindBinaryPath { name, search_paths }) => {
+            assert_eq!(name, "a");
+            assert_eq!(search_paths, env::split_paths(&f.paths).collect::<Vec<_>>());
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[tokio::test]
+#[cfg(unix)]
+async fn test_which_non_executable_error() {
+    use std::error::Error as _;
+    use std::os::unix::fs::PermissionsExt;
+
+    let f = TestFixture::new().await;
+    let b = f.touch("b/another", "").await.unwrap();
+    tokio::fs::set_permissions(&b, std::fs::Permissions::from_mode(0o644))
+        .await
+        .unwrap();
+    let err = _which(&f, "another").await.unwrap_err();
+    assert!(err.source().is_some());
+    assert!(err.to_string().ends_with("is not executable (mode 0644)"));
+    match err {
+        async_which::Error::NotExecutable {
+            name,
+            candidate,
+            mode,
+            ..
+        } => {
+            assert_eq!(name, "another");
+            assert_eq!(candidate, b);
+            assert_eq!(mode, Some(0o644));
+        }
+        other => panic!("unexpected error: {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_which_invalid_name() {
+    let f = TestFixture::new().await;
+    assert!(matches!(
+        _which(&f, "bin\0").await,
+        Err(async_which::Error::InvalidName { .. })
+    ));
+    assert!(matches!(
+        _which(&f, "").await,
+        Err(async_which::Error::InvalidName { .. })
+    ));
+}
+
+#[tokio::test]
+async fn test_which_path_unset() {
+    let f = TestFixture::new().await;
+    assert!(matches!(
+        async_which::which_in(BIN_NAME, Option::<&str>::None, f.tempdir.path()).await,
+        Err(async_which::Error::PathUnset { .. })
+    ));
+}
+
+#[tokio::test]
+#[cfg(unix)]
+async fn test_which_explain() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let f = TestFixture::new().await;
+    let a = f.touch("a/another", "").await.unwrap();
+    tokio::fs::set_permissions(&a, std::fs::Permissions::from_mode(0o644))
+        .await
+        .unwrap();
+    let b = f.mk_bin("b/another", "").await.unwrap();
+
+    let report = async_which::WhichConfig::new()
+        .binary_name("another".into())
+        .custom_path_list(f.paths.clone())
+        .explain()
+        .await
+        .unwrap();
+
+    assert_eq!(report.found(), Some(b.as_path()));
+    assert_eq!(report.probes.len(), 4);
+    assert_eq!(report.probes[0].path_index, Some(0));
+    assert_eq!(
+        report.probes[0].checks,
+        vec![
+            async_which::CheckOutcome {
+                checker: "existed".into(),
+                verdict: async_which::Verdict::Accepted,
+            },
+            async_which::CheckOutcome {
+                checker: "executable".into(),
+                verdict: async_which::Verdict::Rejected(async_which::Rejection::NotExecutable {
+                    mode: Some(0o644),
+                    errno: Some(libc::EACCES),
+                }),
+            },
+        ]
+    );
+    assert!(report.probes[1].accepted());
+    assert_eq!(
+        report.probes[2].checks[0].verdict.rejection(),
+        Some(&async_which::Rejection::Missing)
+    );
+    assert!(report
+        .to_string()
+        .contains("executable: is not executable (mode 0644)"));
+}
+
+#[tokio::test]
+#[cfg(all(unix, feature = "tracing"))]
+async fn test_which_traces_checkers() {
+    use std::sync::{Arc, Mutex};
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata};
+
+    #[derive(Default)]
+    struct Checkers(Arc<Mutex<Vec<String>>>);
+
+    impl Visit for Checkers {
+        fn record_debug(&mut self, _field: &Field, _value: &dyn std::fmt::Debug) {}
+
+        fn record_str(&mut self, field: &Field, value: &str) {
+            if field.name() == "checker" {
+                self.0.lock().unwrap().push(value.to_owned());
+            }
+        }
+    }
+
+    struct Collector(Arc<Mutex<Vec<String>>>);
+
+    impl tracing::Subscriber for Collector {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+        fn new_span(&self, _: &Attributes<'_>) -> Id {
+            Id::from_u64(1)
+        }
+        fn record(&self, _: &Id, _: &Record<'_>) {}
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+        fn event(&self, event: &Event<'_>) {
+            event.record(&mut Checkers(self.0.clone()));
+        }
+        fn enter(&self, _: &Id) {}
+        fn exit(&self, _: &Id) {}
+    }
+
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let _guard = tracing::subscriber::set_default(Collector(seen.clone()));
+
+    let f = TestFixture::new().await;
+    _which(&f, BIN_NAME).await.unwrap();
+
+    let seen = seen.lock().unwrap();
+    assert!(seen.iter().any(|c| c == "existed"));
+    assert!(seen.iter().any(|c| c == "executable"));
+}
+
+#[tokio::test]
+#[cfg(unix)]
+async fn test_which_rejects_directory() {
+    let f = TestFixture::new().await;
+    tokio::fs::create_dir(f.tempdir.path().join("b/another"))
+        .await
+        .unwrap();
+    match _which(&f, "another").await {
+        Err(async_which::Error::Rejected { reason, .. }) => {
+            assert_eq!(reason, async_which::Rejection::Directory)
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[tokio::test]
+#[cfg(unix)]
+async fn test_which_rejects_broken_symlink() {
+    let f = TestFixture::new().await;
+    tokio::fs::symlink(
+        f.tempdir.path().join("nowhere"),
+        f.tempdir.path().join("b/another"),
+    )
+    .await
+    .unwrap();
+    match _which(&f, "another").await {
+        Err(async_which::Error::Rejected { reason, .. }) => {
+            assert_eq!(reason, async_which::Rejection::BrokenSymlink)
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[tokio::test]
+#[cfg(unix)]
+async fn test_which_path_entry_is_a_file() {
+    use std::error::Error as _;
+
+    let f = TestFixture::new().await;
+    let paths = env::join_paths([f.bins[0].clone(), f.tempdir.path().join("b")]).unwrap();
+    assert!(matches!(
+        async_which::which_in("another", Some(&paths), ".").await,
+        Err(async_which::Error::CannotFindBinaryPath { .. })
+    ));
+
+    // A rejection that could not be inspected keeps the OS error as its source.
+    let looping = f.tempdir.path().join("b/another");
+    tokio::fs::symlink(&looping, &looping).await.unwrap();
+    let error = async_which::which_in("another", Some(&paths), ".")
+        .await
+        .unwrap_err();
+    match &error {
+        async_which::Error::Rejected {
+            candidate,
+            reason: async_which::Rejection::Io(_),
+            ..
+        } => assert_eq!(*candidate, looping),
+        other => panic!("unexpected result: {:?}", other),
+    }
+    let source = error.source().unwrap().downcast_ref::<io::Error>().unwrap();
+    assert_eq!(source.raw_os_error(), Some(libc::ELOOP));
+}
+
+#[tokio::test]
+#[cfg(unix)]
+async fn test_which_config_custom_checker() {
+    use async_which::checker::checker_fn;
+
+    let f = TestFixture::new().await;
+    let skip_a = checker_fn(|p| async move { !p.parent().unwrap().ends_with("a") }).named("skip-a");
+
+    let result = async_which::WhichConfig::new()
+        .binary_name(BIN_NAME.into())
+        .custom_path_list(f.paths.clone())
+        .add_checker(skip_a)
+        .first_result()
+        .await
+        .unwrap();
+    assert_eq!(result, f.bins[3]);
+}
+
+#[tokio::test]
+#[cfg(unix)]
+async fn test_which_in_with_checker_combinators() {
+    use async_which::checker::{checker_fn, CheckerExt, Rejection, Verdict};
+
+    let f = TestFixture::new().await;
+    let in_dir = |dir: &'static str| {
+        checker_fn(move |p: PathBuf| async move { p.parent().unwrap().ends_with(dir) })
+    };
+    let veto = checker_fn(|_| async { Verdict::Rejected(Rejection::Other("vetoed".into())) });
+
+    let not_a_or_b = in_dir("a").or(in_dir("b")).not();
+    assert_eq!(
+        async_which::which_in_with_checker(BIN_NAME, Some(&f.paths), ".", not_a_or_b)
+            .await
+            .unwrap(),
+        f.bins[6]
+    );
+
+    let both = in_dir("b").and(in_dir("b"));
+    assert_eq!(
+        async_which::which_in_with_checker(BIN_NAME, Some(&f.paths), ".", both)
+            .await
+            .unwrap(),
+        f.bins[3]
+    );
+
+    match async_which::which_in_with_checker(BIN_NAME, Some(&f.paths), ".", veto).await {
+        Err(async_which::Error::Rejected { reason, .. }) => {
+            assert_eq!(reason, Rejection::Other("vetoed".into()))
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_composite_checker_evaluation() {
+    use async_which::checker::{checker_fn, CompositeChecker, Evaluation};
+    use async_which::checker::{Checker, Rejection, Verdict};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    let build = |evaluation| {
+        let ran = Arc::new(AtomicUsize::new(0));
+        let counter = ran.clone();
+        let expensive = checker_fn(move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            async { true }
+        })
+        .named("expensive")
+        .with_cost(100);
+        let reject = checker_fn(|_| async { false }).named("reject");
+        let composite = CompositeChecker::new()
+            .add_checker(Box::new(expensive))
+            .add_checker(Box::new(reject))
+            .evaluation(evaluation);
+        (composite, ran)
+    };
+
+    let path = Path::new("whatever");
+
+    let (composite, ran) = build(Evaluation::Sequential);
+    assert_eq!(
+        composite.check(path).await,
+        Verdict::Rejected(Rejection::Unspecified)
+    );
+    assert_eq!(ran.load(Ordering::SeqCst), 1);
+
+    let (composite, ran) = build(Evaluation::CostOrdered);
+    assert!(!composite.is_valid(path).await);
+    assert_eq!(ran.load(Ordering::SeqCst), 0);
+    let outcomes = composite.explain(path).await;
+    assert_eq!(outcomes.len(), 1);
+    assert_eq!(outcomes[0].checker, "reject");
+
+    let (composite, ran) = build(Evaluation::Concurrent);
+    assert_eq!(composite.explain(path).await.len(), 2);
+    assert_eq!(ran.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+#[cfg(unix)]
+async fn test_which_all_with_near_misses() {
+    use async_which::{Lookup, Rejection};
+    use futures::TryStreamExt;
+
+    let f = TestFixture::new().await;
+    let a = f.touch("a/another", "").await.unwrap();
+    let b = f.mk_bin("b/another", "").await.unwrap();
+    tokio::fs::create_dir(f.tempdir.path().join("c/another"))
+        .await
+        .unwrap();
+
+    let lookups: Vec<Lookup> =
+        async_which::which_in_all_with_near_misses("another", Some(&f.paths), ".")
+            .try_collect()
+            .await
+            .unwrap();
+
+    assert_eq!(lookups.len(), 3);
+    assert!(matches!(
+        &lookups[0],
+        Lookup::NearMiss { path, reason: Rejection::NotExecutable { .. } } if *path == a
+    ));
+    assert_eq!(lookups[1], Lookup::Found(b));
+    assert!(matches!(
+        &lookups[2],
+        Lookup::NearMiss {
+            reason: Rejection::Directory,
+            ..
+        }
+    ));
+
+    // Nothing exists under a path entry that is a regular file.
+    let paths = env::join_paths([f.bins[0].clone(), f.tempdir.path().join("b")]).unwrap();
+    let lookups: Vec<Lookup> =
+        async_which::which_in_all_with_near_misses("another", Some(&paths), ".")
+            .try_collect()
+            .await
+            .unwrap();
+    assert_eq!(
+        lookups,
+        vec![Lookup::Found(f.tempdir.path().join("b/another"))]
+    );
+}
+
+#[tokio::test]
+#[cfg(all(unix, feature = "regex"))]
+async fn test_which_re_in_reporting_errors() {
+    let f = TestFixture::new().await;
+    f.mk_bin("b/bin_1", "").await.unwrap();
+    let missing = f.tempdir.path().join("missing");
+    let mut paths = env::split_paths(&f.paths).collect::<Vec<_>>();
+    paths.insert(1, missing.clone());
+    let paths = env::join_paths(paths).unwrap();
+    let re = Regex::new(r"bin_\d").unwrap();
+
+    let results: Vec<_> = async_which::which_re_in_reporting_errors(&re, &paths)
+        .collect()
+        .await;
+    assert_eq!(results.len(), 2);
+    match &results[0] {
+        Err(async_which::Error::UnreadableDirectory { path, source }) => {
+            assert_eq!(*path, missing);
+            assert_eq!(source.kind(), io::ErrorKind::NotFound);
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+    assert_eq!(
+        results[1].as_ref().unwrap(),
+        &f.tempdir.path().join("b/bin_1")
+    );
+
+    let silent: Vec<PathBuf> = async_which::which_re_in(&re, &paths)
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(silent, vec![f.tempdir.path().join("b/bin_1")]);
+}
+
+#[tokio::test]
+#[cfg(all(unix, feature = "regex"))]
+async fn test_first_result_re_skips_unreadable_dirs() {
+    let f = TestFixture::new().await;
+    let good = f.mk_bin("b/tool1", "").await.unwrap();
+    let missing = f.tempdir.path().join("missing");
+    let paths = env::join_paths([missing.clone(), f.tempdir.path().join("b")]).unwrap();
+    let config = || {
+        async_which::WhichConfig::new()
+            .system_cwd(false)
+            .custom_path_list(paths.clone())
+            .report_unreadable_dirs(true)
+    };
+
+    let found = config()
+        .regex(Regex::new(r"^tool\d$").unwrap())
+        .first_result()
+        .await
+        .unwrap();
+    assert_eq!(found, good);
+
+    match config()
+        .regex(Regex::new(r"^nothing$").unwrap())
+        .first_result()
+        .await
+    {
+        Err(async_which::Error::UnreadableDirectory { path, .. }) => assert_eq!(path, missing),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[tokio::test]
+#[cfg(all(target_os = "linux", feature = "regex"))]
+async fn test_which_re_bytes_in_non_utf8() {
+    use std::os::unix::ffi::OsStrExt;
+
+    let f = TestFixture::new().await;
+    // "café" encoded as Latin-1.
+    let name = OsStr::from_bytes(b"caf\xe9");
+    let bin = f
+        .mk_bin(&format!("b/{}", name.to_string_lossy()), "")
+        .await
+        .unwrap();
+    tokio::fs::rename(&bin, bin.with_file_name(name))
+        .await
+        .unwrap();
+
+    let re = regex::bytes::Regex::new(r"(?-u)^caf\xE9$").unwrap();
+    let result: Vec<PathBuf> = async_which::which_re_bytes_in(&re, &f.paths)
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(result, vec![f.tempdir.path().join("b").join(name)]);
+
+    let result: Vec<PathBuf> = async_which::WhichConfig::new()
+        .system_cwd(false)
+        .regex_bytes(re)
+        .custom_path_list(f.paths.clone())
+        .all_results()
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(result.len(), 1);
+
+    let unicode = Regex::new("^caf").unwrap();
+    let result: Vec<PathBuf> = async_which::which_re_in(unicode, &f.paths)
+        .try_collect()
+        .await
+        .unwrap();
+    assert!(result.is_empty());
+}
+
+#[tokio::test]
+#[cfg(unix)]
+async fn test_which_shadowed() {
+    let f = TestFixture::new().await;
+    tokio::fs::remove_file(&f.bins[6]).await.unwrap();
+    tokio::fs::symlink(&f.bins[0], &f.bins[6]).await.unwrap();
+
+    let shadowing = async_which::WhichConfig::new()
+        .binary_name(BIN_NAME.into())
+        .custom_path_list(f.paths.clone())
+        .shadowed()
+        .await
+        .unwrap();
+
+    assert_eq!(
+        shadowing.winner,
+        async_which::Located {
+            path: f.bins[0].clone(),
+            path_index: Some(0),
+        }
+    );
+    assert_eq!(
+        shadowing.shadowed,
+        vec![
+            async_which::Shadowed {
+                path: f.bins[3].clone(),
+                path_index: Some(1),
+                same_file: false,
+            },
+            async_which::Shadowed {
+                path: f.bins[6].clone(),
+                path_index: Some(2),
+                same_file: true,
+            },
+        ]
+    );
+    assert_eq!(shadowing.distinct().count(), 1);
+}
+
+#[tokio::test]
+async fn test_which_shadowed_not_found() {
+    let f = TestFixture::new().await;
+    let result = async_which::WhichConfig::new()
+        .binary_name("missing".into())
+        .custom_path_list(f.paths.clone())
+        .shadowed()
+        .await;
+    assert!(matches!(
+        result,
+        Err(async_which::Error::CannotFindBinaryPath { .. })
+    ));
+
+    // A path entry that is a regular file shadows nothing.
+    let paths = env::join_paths([f.bins[0].clone(), f.tempdir.path().join("b")]).unwrap();
+    let result = async_which::WhichConfig::new()
+        .binary_name("missing".into())
+        .custom_path_list(paths.clone())
+        .shadowed()
+        .await;
+    assert!(matches!(
+        result,
+        Err(async_which::Error::CannotFindBinaryPath { .. })
+    ));
+    #[cfg(unix)]
+    {
+        let shadowing = async_which::WhichConfig::new()
+            .binary_name(BIN_NAME.into())
+            .custom_path_list(paths)
+            .shadowed()
+            .await
+            .unwrap();
+        assert_eq!(shadowing.winner.path, f.bins[3]);
+        assert!(shadowing.shadowed.is_empty());
+    }
+}
+
+#[tokio::test]
+#[cfg(unix)]
+async fn test_path_audit() {
+    use async_which::{PathAudit, PathIssue, Severity};
+    use std::os::unix::fs::PermissionsExt;
+
+    let f = TestFixture::new().await;
+    let root = f.tempdir.path();
+    let a = root.join("a");
+    let c = root.join("c");
+    tokio::fs::set_permissions(&c, std::fs::Permissions::from_mode(0o777))
+        .await
+        .unwrap();
+    tokio::fs::symlink(&a, root.join("link")).await.unwrap();
+    let paths = env::join_paths([
+        a.clone(),
+        PathBuf::from("."),
+        root.join("missing"),
+        f.bins[3].clone(),
+        a.clone(),
+        root.join("link"),
+        c.clone(),
+        PathBuf::from("relative"),
+    ])
+    .unwrap();
+
+    let audit = PathAudit::new(&paths).await;
+    let issues: Vec<(usize, PathIssue)> = audit
+        .findings
+        .iter()
+        .map(|finding| (finding.index, finding.issue.clone()))
+        .collect();
+
+    assert_eq!(
+        issues,
+        vec![
+            (1, PathIssue::CurrentDir),
+            (2, PathIssue::Missing),
+            (3, PathIssue::NotADirectory),
+            (4, PathIssue::Duplicate { of: 0 }),
+            (
+                5,
+                PathIssue::SymlinkDuplicate {
+                    of: 0,
+                    canonical: a.canonicalize().unwrap(),
+                }
+            ),
+            (6, PathIssue::WorldWritable { mode: 0o777 }),
+            (7, PathIssue::Relative),
+            (7, PathIssue::Missing),
+        ]
+    );
+    assert_eq!(audit.worst(), Some(Severity::Error));
+    assert_eq!(audit.at_least(Severity::Error).count(), 2);
+    assert_eq!(audit.findings_for(0).count(), 0);
+
+    // An unset variable has no entries, rather than one empty entry.
+    let unset = PathAudit::env_var("ASYNC_WHICH_TEST_UNSET_PATH").await;
+    assert!(unset.entries.is_empty());
+    assert_eq!(unset.worst(), None);
+    assert_eq!(PathAudit::new("").await.worst(), Some(Severity::Error));
+}
+
+#[tokio::test]
+#[cfg(unix)]
+async fn test_which_secure() {
+    use async_which::checker::{Checker, SecureChecker};
+    use async_which::Rejection;
+    use std::os::unix::fs::PermissionsExt;
+
+    let f = TestFixture::new().await;
+    // Start from known permissions rather than whatever the umask left.
+    for dir in ["a", "b", "c"] {
+        tokio::fs::set_permissions(
+            f.tempdir.path().join(dir),
+            std::fs::Permissions::from_mode(0o755),
+        )
+        .await
+        .unwrap();
+    }
+    let a = f.tempdir.path().join("a");
+    tokio::fs::set_permissions(&a, std::fs::Permissions::from_mode(0o777))
+        .await
+        .unwrap();
+    tokio::fs::set_permissions(&f.bins[3], std::fs::Permissions::from_mode(0o757))
+        .await
+        .unwrap();
+
+    let result = async_which::WhichConfig::new()
+        .binary_name(BIN_NAME.into())
+        .custom_path_list(f.paths.clone())
+        .secure(true)
+        .first_result()
+        .await
+        .unwrap();
+    assert_eq!(result, f.bins[6]);
+
+    let verdict = SecureChecker::new().check(&f.bins[0]).await;
+    assert_eq!(
+        verdict.rejection(),
+        Some(&Rejection::WorldWritable {
+            path: a.canonicalize().unwrap(),
+            mode: 0o777,
+        })
+    );
+
+    tokio::fs::set_permissions(&a, std::fs::Permissions::from_mode(0o1777))
+        .await
+        .unwrap();
+    assert!(SecureChecker::new().check(&f.bins[0]).await.is_accepted());
+}
+
+#[test]
+fn test_binary_kind_from_header() {
+    use async_which::BinaryKind;
+
+    assert_eq!(BinaryKind::from_header(b""), BinaryKind::Empty);
+    assert_eq!(
+        BinaryKind::from_header(b"\x7fELF\x02\x01\x01\x00"),
+        BinaryKind::Elf
+    );
+    assert_eq!(BinaryKind::from_header(b"MZ\x90\x00"), BinaryKind::Pe);
+    assert_eq!(
+        BinaryKind::from_header(b"\xcf\xfa\xed\xfe"),
+        BinaryKind::MachO
+    );
+    assert_eq!(
+        BinaryKind::from_header(b"\xca\xfe\xba\xbe\x00\x00\x00\x02"),
+        BinaryKind::MachO
+    );
+    assert_eq!(
+        BinaryKind::from_header(b"\xca\xfe\xba\xbe\x00\x00\x00\x41"),
+        BinaryKind::Unknown
+    );
+    assert_eq!(
+        BinaryKind::from_header(b"\0asm\x01\0\0\0"),
+        BinaryKind::Wasm
+    );
+    assert_eq!(BinaryKind::from_header(b"#!/bin/sh"), BinaryKind::Script);
+    assert_eq!(BinaryKind::from_header(b"\x7fEL"), BinaryKind::Unknown);
+}
+
+#[tokio::test]
+#[cfg(unix)]
+async fn test_which_with_format_checker() {
+    use async_which::checker::FormatChecker;
+    use async_which::{BinaryKind, Rejection};
+
+    let f = TestFixture::new().await;
+    // a/bin stays empty, like an interrupted download.
+    tokio::fs::write(&f.bins[3], "#!/bin/sh\n").await.unwrap();
+    tokio::fs::write(&f.bins[6], b"\x7fELF\x02\x01\x01\x00")
+        .await
+        .unwrap();
+
+    let result = async_which::WhichConfig::new()
+        .binary_name(BIN_NAME.into())
+        .custom_path_list(f.paths.clone())
+        .add_checker(FormatChecker::new())
+        .first_result()
+        .await
+        .unwrap();
+    assert_eq!(result, f.bins[3]);
+
+    let error = async_which::WhichConfig::new()
+        .binary_name(BIN_NAME.into())
+        .custom_path_list(f.tempdir.path().join("a").into_os_string())
+        .add_checker(FormatChecker::new())
+        .first_result()
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        async_which::Error::Rejected {
+            reason: Rejection::UnexpectedFormat(BinaryKind::Empty),
+            ..
+        }
+    ));
+
+    let result = async_which::which_in_with_checker(
+        BIN_NAME,
+        Some(&f.paths),
+        ".",
+        FormatChecker::new().require_binary(true),
+    )
+    .await
+    .unwrap();
+    assert_eq!(result, f.bins[6]);
+
+    let path = async_which::Path::new_in(BIN_NAME, Some(&f.paths), ".")
+        .await
+        .unwrap();
+    assert_eq!(path.binary_kind().await.unwrap(), BinaryKind::Empty);
+}
+
+#[test]
+fn test_elf_arch() {
+    use async_which::ElfArch;
+
+    let x86_64 = ElfArch::for_target("x86_64-unknown-linux-gnu").unwrap();
+    let mut header = b"\x7fELF\x02\x01\x01\x00\0\0\0\0\0\0\0\0\x02\x00\x3e\x00".to_vec();
+    assert_eq!(ElfArch::from_header(&header), Some(x86_64));
+    assert_eq!(ElfArch::from_header(&header[..19]), None);
+
+    header[18] = 0xb7;
+    let aarch64 = ElfArch::from_header(&header).unwrap();
+    assert_eq!(
+        Some(aarch64),
+        ElfArch::for_target("aarch64-unknown-linux-gnu")
+    );
+    assert_eq!(aarch64.to_string(), "aarch64 (64-bit little-endian)");
+
+    let x32 = ElfArch::for_target("x86_64-unknown-linux-gnux32").unwrap();
+    assert!(!x32.is_64_bit);
+    assert_eq!(ElfArch::for_target("wasm32-unknown-unknown"), None);
+}
+
+#[tokio::test]
+#[cfg(unix)]
+async fn test_which_with_arch_checker() {
+    use async_which::checker::ArchChecker;
+    use async_which::{BinaryKind, ElfArch, Rejection};
+
+    let f = TestFixture::new().await;
+    let elf = |machine: u8| {
+        let mut header = b"\x7fELF\x02\x01\x01\x00\0\0\0\0\0\0\0\0\x02\x00".to_vec();
+        header.extend([machine, 0]);
+        header
+    };
+    tokio::fs::write(&f.bins[0], elf(0xb7)).await.unwrap();
+    tokio::fs::write(&f.bins[3], elf(0x3e)).await.unwrap();
+
+    let checker = ArchChecker::for_target("x86_64-unknown-linux-gnu").unwrap();
+    let result = async_which::which_in_with_checker(BIN_NAME, Some(&f.paths), ".", checker)
+        .await
+        .unwrap();
+    assert_eq!(result, f.bins[3]);
+
+    let error = async_which::WhichConfig::new()
+        .binary_name(BIN_NAME.into())
+        .custom_path_list(f.tempdir.path().join("a").into_os_string())
+        .add_checker(ArchChecker::for_target("x86_64-unknown-linux-gnu").unwrap())
+        .first_result()
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        async_which::Error::Rejected {
+            reason: Rejection::WrongArchitecture { found, .. },
+            ..
+        } if Some(found) == ElfArch::for_target("aarch64-linux-android")
+    ));
+
+    // A truncated header, and one with an invalid class byte, are malformed ELF files.
+    let mut bad_class = elf(0x3e);
+    bad_class[4] = 9;
+    for header in [b"\x7fELF\x02\x01".to_vec(), bad_class] {
+        tokio::fs::write(&f.bins[6], header).await.unwrap();
+        let error = async_which::WhichConfig::new()
+            .binary_name(BIN_NAME.into())
+            .custom_path_list(f.tempdir.path().join("c").into_os_string())
+            .add_checker(ArchChecker::for_target("x86_64-unknown-linux-gnu").unwrap())
+            .first_result()
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            async_which::Error::Rejected {
+                reason: Rejection::UnexpectedFormat(BinaryKind::Elf),
+                ..
+            }
+        ));
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        use async_which::checker::Checker;
+
+        let test_binary = env::current_exe().unwrap();
+        assert!(ArchChecker::host().check(&test_binary).await.is_accepted());
+    }
+}
+
+#[test]
+fn test_shebang_parse() {
+    use async_which::Shebang;
+
+    let shebang = Shebang::parse(b"#!/usr/bin/env -S python3 -u\nprint()").unwrap();
+    assert_eq!(shebang.interpreter, PathBuf::from("/usr/bin/env"));
+    assert_eq!(shebang.args, vec!["-S", "python3", "-u"]);
+    assert_eq!(shebang.env_program(), Some("python3".into()));
+
+    let shebang = Shebang::parse(b"#! /bin/sh -e\n").unwrap();
+    assert_eq!(shebang.interpreter, PathBuf::from("/bin/sh"));
+    assert_eq!(shebang.env_program(), None);
+
+    let shebang = Shebang::parse(b"#!/usr/bin/env -i LANG=C -Sruby -w").unwrap();
+    assert_eq!(shebang.env_program(), Some("ruby".into()));
+
+    assert_eq!(Shebang::parse(b"echo hi"), None);
+    assert_eq!(Shebang::parse(b"#!\n"), None);
+}
+
+#[tokio::test]
+#[cfg(unix)]
+async fn test_interpreter_chain() {
+    use async_which::checker::ShebangChecker;
+    use async_which::{Rejection, Verdict};
+
+    let f = TestFixture::new().await;
+    let env_path = f.tempdir.path().join("a").join("env");
+    tokio::fs::symlink("/usr/bin/env", &env_path).await.unwrap();
+    let python = f.mk_bin("c/python3", "").await.unwrap();
+    tokio::fs::write(&f.bins[0], format!("#!{} python3 -u\n", env_path.display()))
+        .await
+        .unwrap();
+    tokio::fs::write(&f.bins[3], "#!/nonexistent/python2\n")
+        .await
+        .unwrap();
+
+    let chain = async_which::interpreter_chain_in(&f.bins[0], Some(&f.paths))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(chain.interpreters.len(), 1);
+    assert_eq!(chain.interpreters[0].path, python);
+    assert_eq!(chain.program(), Some(python.as_path()));
+
+    let chain = async_which::interpreter_chain_in(&f.bins[3], Some(&f.paths))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(chain.program(), None);
+    assert_eq!(
+        chain.rejection(),
+        Some(Rejection::BrokenInterpreter {
+            interpreter: PathBuf::from("/nonexistent/python2"),
+            reason: Box::new(Rejection::Missing),
+        })
+    );
+    assert_eq!(
+        chain.interpreters[0].verdict,
+        Verdict::Rejected(Rejection::Missing)
+    );
+
+    assert!(
+        async_which::interpreter_chain_in(&f.bins[6], Some(&f.paths))
+            .await
+            .unwrap()
+            .is_none()
+    );
+
+    tokio::fs::remove_file(&python).await.unwrap();
+    let result = async_which::WhichConfig::new()
+        .binary_name(BIN_NAME.into())
+        .custom_path_list(f.paths.clone())
+        .add_checker(ShebangChecker::new().path_list(f.paths.clone()))
+        .first_result()
+        .await
+        .unwrap();
+    assert_eq!(result, f.bins[6]);
+}
+
+#[test]
+#[cfg(feature = "version")]
+fn test_version_req() {
+    use async_which::{Version, VersionReq};
+
+    let v = |s: &str| s.parse::<Version>().unwrap();
+    assert_eq!(v("v3.20"), Version::new(3, 20, 0));
+    assert_eq!(v("3.27.4-rc1"), Version::new(3, 27, 4));
+    assert!("three".parse::<Version>().is_err());
+
+    let req = VersionReq::parse(">=3.20, <4").unwrap();
+    assert!(req.matches(&v("3.20.0")));
+    assert!(req.matches(&v("3.99")));
+    assert!(!req.matches(&v("3.19.9")));
+    assert!(!req.matches(&v("4.0.0")));
+    assert_eq!(req.to_string(), ">=3.20, <4");
+
+    assert!(VersionReq::parse("1.2").unwrap().matches(&v("1.9.0")));
+    assert!(!VersionReq::parse("1.2").unwrap().matches(&v("2.0.0")));
+    assert!(!VersionReq::parse("^0.2").unwrap().matches(&v("0.3.0")));
+    assert!(VersionReq::parse("~1.2.3").unwrap().matches(&v("1.2.9")));
+    assert!(!VersionReq::parse("~1.2.3").unwrap().matches(&v("1.3.0")));
+    assert!(VersionReq::parse("=3.x").unwrap().matches(&v("3.1.4")));
+    assert!(!VersionReq::parse(">3.20").unwrap().matches(&v("3.20.5")));
+    assert!(VersionReq::parse("<=3.20").unwrap().matches(&v("3.20.5")));
+    assert!(VersionReq::parse("*").unwrap().matches(&v("0.0.1")));
+    assert!(VersionReq::parse(">=").is_err());
+    assert!(VersionReq::parse("").is_err());
+}
+
+#[tokio::test]
+#[cfg(all(unix, feature = "version"))]
+async fn test_which_version() {
+    use async_which::checker::VersionChecker;
+    use async_which::{Rejection, Version, VersionReq};
+    use std::time::Duration;
+
+    let f = TestFixture::new().await;
+    tokio::fs::write(&f.bins[0], "#!/bin/sh\necho \"bin version 3.19.1\"\n")
+        .await
+        .unwrap();
+    tokio::fs::write(&f.bins[3], "#!/bin/sh\necho \"bin version 3.27.4\" >&2\n")
+        .await
+        .unwrap();
+    let result = async_which::WhichConfig::new()
+        .binary_name(BIN_NAME.into())
+        .custom_path_list(f.paths.clone())
+        .version_req(VersionReq::parse(">=3.20").unwrap())
+        .first_result()
+        .await
+        .unwrap();
+    assert_eq!(result, f.bins[3]);
+
+    let error = async_which::WhichConfig::new()
+        .binary_name(BIN_NAME.into())
+        .custom_path_list(f.paths.clone())
+        .version_req(VersionReq::parse(">=4").unwrap())
+        .first_result()
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        async_which::Error::Rejected {
+            reason: Rejection::VersionMismatch { found, .. },
+            ..
+        } if found == Version::new(3, 19, 1)
+    ));
+
+    tokio::fs::write(&f.bins[6], "#!/bin/sh\nsleep 5\n")
+        .await
+        .unwrap();
+    let checker =
+        VersionChecker::new(VersionReq::parse("*").unwrap()).timeout(Duration::from_millis(100));
+    let result = async_which::which_in_with_checker(
+        BIN_NAME,
+        Some(f.tempdir.path().join("c")),
+        ".",
+        checker,
+    )
+    .await;
+    assert!(matches!(
+        result,
+        Err(async_which::Error::Rejected {
+            reason: Rejection::UnknownVersion(_),
+            ..
+        })
+    ));
+}
+
+#[tokio::test]
+#[cfg(all(unix, feature = "checksum"))]
+async fn test_which_with_checksum_checker() {
+    use async_which::checker::ChecksumChecker;
+    use async_which::{Rejection, Sha256Digest};
+
+    // SHA-256 of "vetted\n".
+    const VETTED: &str = "9e42546d1bc720f71f08ff1527a5271c2e593934dd84d8313259de0b14c76309";
+
+    let f = TestFixture::new().await;
+    tokio::fs::write(&f.bins[0], "tampered\n").await.unwrap();
+    tokio::fs::write(&f.bins[3], "vetted\n").await.unwrap();
+    let vetted = async_which::sha256_file(&f.bins[3]).await.unwrap();
+    assert_eq!(vetted, VETTED.parse::<Sha256Digest>().unwrap());
+    assert_eq!(vetted.to_string(), VETTED);
+
+    let sums = f.tempdir.path().join("vetted.sha256");
+    tokio::fs::write(&sums, format!("# vetted tools\n{}  bin\n", vetted))
+        .await
+        .unwrap();
+    let checker = ChecksumChecker::from_sums_file(&sums).await.unwrap();
+    let result = async_which::which_in_with_checker(BIN_NAME, Some(&f.paths), ".", checker)
+        .await
+        .unwrap();
+    assert_eq!(result, f.bins[3]);
+
+    let error = async_which::WhichConfig::new()
+        .binary_name(BIN_NAME.into())
+        .custom_path_list(f.tempdir.path().join("a").into_os_string())
+        .add_checker(ChecksumChecker::new().allow(vetted))
+        .first_result()
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        async_which::Error::Rejected {
+            reason: Rejection::UnknownChecksum(_),
+            ..
+        }
+    ));
+
+    assert!(ChecksumChecker::from_sums("not-a-digest  bin\n").is_err());
+}
+
+#[tokio::test]
+#[cfg(unix)]
+async fn test_executable_checker_access_modes() {
+    use async_which::checker::{AccessMode, Checker, ExecutableChecker};
+    use async_which::Rejection;
+    use std::os::unix::fs::PermissionsExt;
+
+    let f = TestFixture::new().await;
+    let set_mode = |path: PathBuf, mode: u32| async move {
+        tokio::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))
+            .await
+            .unwrap()
+    };
+    set_mode(f.bins[0].clone(), 0o644).await;
+    set_mode(f.bins[3].clone(), 0o701).await;
+
+    for access in [
+        AccessMode::Real,
+        AccessMode::Effective,
+        AccessMode::Metadata,
+    ] {
+        let checker = ExecutableChecker::new().access_mode(access);
+        assert!(
+            checker.check(&f.bins[3]).await.is_accepted(),
+            "{:?}",
+            access
+        );
+        assert!(matches!(
+            checker.check(&f.bins[0]).await.rejection(),
+            Some(Rejection::NotExecutable {
+                mode: Some(0o644),
+                ..
+            })
+        ));
+        assert_eq!(
+            checker
+                .check(&f.tempdir.path().join("missing"))
+                .await
+                .rejection(),
+            Some(&Rejection::Missing)
+        );
+    }
+
+    let result = async_which::WhichConfig::new()
+        .binary_name(BIN_NAME.into())
+        .custom_path_list(f.paths.clone())
+        .access_mode(AccessMode::Metadata)
+        .first_result()
+        .await
+        .unwrap();
+    assert_eq!(result, f.bins[3]);
+}
+
+#[tokio::test]
+#[cfg(unix)]
+async fn test_cached_checker() {
+    use async_which::checker::{checker_fn, Checker, CheckerExt};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    let f = TestFixture::new().await;
+    let runs = Arc::new(AtomicUsize::new(0));
+    let counted = Arc::clone(&runs);
+    let cached = checker_fn(move |_| {
+        counted.fetch_add(1, Ordering::SeqCst);
+        async { true }
+    })
+    .cached();
+
+    for _ in 0..3 {
+        let result =
+            async_which::which_in_with_checker(BIN_NAME, Some(&f.paths), ".", cached.clone())
+                .await
+                .unwrap();
+        assert_eq!(result, f.bins[0]);
+    }
+    assert_eq!(runs.load(Ordering::SeqCst), 1);
+
+    tokio::fs::write(&f.bins[0], "changed").await.unwrap();
+    assert!(cached.check(&f.bins[0]).await.is_accepted());
+    assert_eq!(runs.load(Ordering::SeqCst), 2);
+
+    cached.invalidate(&f.bins[0]);
+    assert!(cached.check(&f.bins[0]).await.is_accepted());
+    assert_eq!(runs.load(Ordering::SeqCst), 3);
+
+    cached.clear();
+    assert!(cached.check(&f.bins[0]).await.is_accepted());
+    assert_eq!(runs.load(Ordering::SeqCst), 4);
+
+    assert!(cached
+        .check(&f.tempdir.path().join("missing"))
+        .await
+        .is_accepted());
+    assert!(cached
+        .check(&f.tempdir.path().join("missing"))
+        .await
+        .is_accepted());
+    assert_eq!(runs.load(Ordering::SeqCst), 6);
+}
+
+#[tokio::test]
+#[cfg(unix)]
+async fn test_symlink_chain_and_policy() {
+    use async_which::checker::{Checker, SymlinkChecker, SymlinkPolicy};
+    use async_which::Rejection;
+
+    let f = TestFixture::new().await;
+    let root = f.tempdir.path().canonicalize().unwrap();
+    let real = f.mk_bin("c/tool-1.2", "").await.unwrap();
+    let alternative = root.join("b").join("tool-alt");
+    tokio::fs::symlink(&real, &alternative).await.unwrap();
+    let tool = root.join("a").join("tool");
+    tokio::fs::symlink("../b/tool-alt", &tool).await.unwrap();
+
+    let chain = async_which::symlink_chain(&tool).await.unwrap();
+    assert_eq!(chain.hops.len(), 2);
+    assert_eq!(chain.hops[0].target, PathBuf::from("../b/tool-alt"));
+    assert_eq!(chain.target(), real.as_path());
+    assert!(!chain.looped);
+    assert_eq!(
+        chain.to_string(),
+        format!("{} -> ../b/tool-alt -> {}", tool.display(), real.display())
+    );
+
+    let looping = root.join("a").join("loop");
+    tokio::fs::symlink("loop", &looping).await.unwrap();
+    assert!(async_which::symlink_chain(&looping).await.unwrap().looped);
+    assert_eq!(
+        SymlinkChecker::new(SymlinkPolicy::Follow)
+            .check(&looping)
+            .await
+            .rejection(),
+        Some(&Rejection::SymlinkLoop)
+    );
+
+    let within_b = SymlinkChecker::new(SymlinkPolicy::WithinRoot(root.join("b")));
+    assert!(matches!(
+        within_b.check(&alternative).await.rejection(),
+        Some(Rejection::EscapesRoot { target, .. }) if *target == real
+    ));
+    let within_root = SymlinkChecker::new(SymlinkPolicy::WithinRoot(root.clone()));
+    assert!(within_root.check(&tool).await.is_accepted());
+
+    // A hop outside the root is rejected even if the chain ends back inside it.
+    let outside = tempfile::tempdir().unwrap();
+    let outside_link = outside.path().canonicalize().unwrap().join("link2");
+    tokio::fs::symlink(&real, &outside_link).await.unwrap();
+    let detour = root.join("a").join("link1");
+    tokio::fs::symlink(&outside_link, &detour).await.unwrap();
+    assert!(matches!(
+        within_root.check(&detour).await.rejection(),
+        Some(Rejection::EscapesRoot { target, .. }) if *target == outside_link
+    ));
+
+    // So is a candidate under a linked directory that leads outside the root.
+    let outside_tool = outside_link.with_file_name("tool");
+    tokio::fs::write(&outside_tool, "").await.unwrap();
+    tokio::fs::symlink(outside.path(), root.join("linked"))
+        .await
+        .unwrap();
+    assert!(matches!(
+        within_root.check(&root.join("linked/tool")).await.rejection(),
+        Some(Rejection::EscapesRoot { target, .. }) if *target == outside_tool
+    ));
+
+    // A hop that cannot be resolved is rejected rather than skipped.
+    let dangling = root.join("a").join("dangling");
+    tokio::fs::symlink(root.join("missing/tool"), &dangling)
+        .await
+        .unwrap();
+    assert!(matches!(
+        within_root.check(&dangling).await.rejection(),
+        Some(Rejection::Io(error)) if error.kind() == io::ErrorKind::NotFound
+    ));
+
+    let result = async_which::WhichConfig::new()
+        .binary_name("tool".into())
+        .custom_path_list(f.paths.clone())
+        .symlink_policy(SymlinkPolicy::Reject)
+        .first_result()
+        .await;
+    assert!(matches!(
+        result,
+        Err(async_which::Error::Rejected {
+            reason: Rejection::Symlink { .. },
+            ..
+        })
+    ));
+
+    let path = async_which::Path::new_in("tool", Some(&f.paths), ".")
+        .await
+        .unwrap();
+    assert_eq!(path.symlink_chain().await.unwrap(), chain);
+}
+
+#[tokio::test]
+#[cfg(unix)]
+async fn test_search_path_provenance() {
+    use async_which::{SearchPath, WhichConfig};
+
+    let f = TestFixture::new().await;
+    let root = f.tempdir.path();
+    env::set_var(
+        "ASYNC_WHICH_TEST_EXTRA_PATH",
+        env::join_paths([root.join("c")]).unwrap(),
+    );
+    let search_path = SearchPath::new()
+        .append_dir("project", root.join("b"))
+        .append_env_var("ASYNC_WHICH_TEST_EXTRA_PATH")
+        .prepend_dirs("vendored", [root.join("missing"), root.join("a")])
+        .append_env_var("ASYNC_WHICH_TEST_UNSET_PATH");
+    let sources: Vec<_> = search_path
+        .entries()
+        .iter()
+        .map(|entry| entry.source.as_str())
+        .collect();
+    assert_eq!(
+        sources,
+        [
+            "vendored",
+            "vendored",
+            "project",
+            "ASYNC_WHICH_TEST_EXTRA_PATH"
+        ]
+    );
+    assert_eq!(
+        search_path.to_path_list().unwrap(),
+        env::join_paths(search_path.dirs()).unwrap()
+    );
+
+    let found = WhichConfig::new()
+        .binary_name(BIN_NAME.into())
+        .search_path(search_path.clone())
+        .first_sourced()
+        .await
+        .unwrap();
+    assert_eq!(found.path, f.bins[0]);
+    assert_eq!(found.entry.as_ref(), search_path.entries().get(1));
+    assert_eq!(
+        found.to_string(),
+        format!("{} (from vendored)", f.bins[0].display())
+    );
+
+    let all: Vec<_> = WhichConfig::new()
+        .binary_name(BIN_NAME.into())
+        .search_path(search_path)
+        .all_sourced()
+        .map(|found| found.unwrap())
+        .collect()
+        .await;
+    let all: Vec<_> = all
+        .iter()
+        .map(|found| (found.path.clone(), found.source().unwrap()))
+        .collect();
+    assert_eq!(
+        all,
+        [
+            (f.bins[0].clone(), "vendored"),
+            (f.bins[3].clone(), "project"),
+            (f.bins[6].clone(), "ASYNC_WHICH_TEST_EXTRA_PATH"),
+        ]
+    );
+
+    let custom = WhichConfig::new()
+        .binary_name(BIN_NAME.into())
+        .custom_path_list(f.paths.clone())
+        .first_sourced()
+        .await
+        .unwrap();
+    assert_eq!(custom.source(), Some("custom"));
+
+    let relative = WhichConfig::new()
+        .binary_name("./b/bin".into())
+        .custom_cwd(root.to_path_buf())
+        .custom_path_list(f.paths.clone())
+        .first_sourced()
+        .await
+        .unwrap();
+    assert_eq!(relative.entry, None);
+}
+
+#[tokio::test]
+#[cfg(all(unix, feature = "rustup"))]
+async fn test_rustup_toolchain_resolution() {
+    use async_which::{Error, Rustup, ToolchainSource};
+
+    let f = TestFixture::new().await;
+    let root = f.tempdir.path().canonicalize().unwrap();
+    let home = root.join("rustup");
+    for toolchain in [
+        "stable-x86_64-test",
+        "nightly-x86_64-test",
+        "1.70.0-x86_64-test",
+    ] {
+        let bin = home.join("toolchains").join(toolchain).join("bin");
+        tokio::fs::create_dir_all(&bin).await.unwrap();
+        f.mk_bin(&format!("rustup/toolchains/{}/bin/cargo", toolchain), "")
+            .await
+            .unwrap();
+    }
+    let project = root.join("project");
+    let pinned = project.join("pinned");
+    let overridden = project.join("overridden");
+    tokio::fs::create_dir_all(pinned.join("src")).await.unwrap();
+    tokio::fs::create_dir_all(&overridden).await.unwrap();
+    tokio::fs::write(
+        pinned.join("rust-toolchain.toml"),
+        "[toolchain]\nchannel = \"1.70.0\"\ncomponents = [\"clippy\"]\n",
+    )
+    .await
+    .unwrap();
+    tokio::fs::write(overridden.join("rust-toolchain"), "1.70.0\n")
+        .await
+        .unwrap();
+    tokio::fs::write(
+        home.join("settings.toml"),
+        format!(
+            "default_host_triple = \"x86_64-test\"\ndefault_toolchain = \"stable\"\n\n[overrides]\n{:?} = \"nightly-x86_64-test\"\n",
+            overridden.display().to_string()
+        ),
+    )
+    .await
+    .unwrap();
+
+    let rustup = Rustup::new(&home, &project);
+    let toolchain = rustup.toolchain().await.unwrap();
+    assert_eq!(toolchain.name, "stable-x86_64-test");
+    assert_eq!(toolchain.source, ToolchainSource::Default);
+
+    let cargo = rustup
+        .clone()
+        .cwd(pinned.join("src"))
+        .which("cargo")
+        .await
+        .unwrap();
+    assert_eq!(
+        cargo.path,
+        home.join("toolchains/1.70.0-x86_64-test/bin/cargo")
+    );
+    assert_eq!(
+        cargo.toolchain.source,
+        ToolchainSource::ToolchainFile(pinned.join("rust-toolchain.toml"))
+    );
+    assert_eq!(
+        cargo.toolchain.sysroot().root(),
+        home.join("toolchains/1.70.0-x86_64-test")
+    );
+
+    let toolchain = rustup.clone().cwd(&overridden).toolchain().await.unwrap();
+    assert_eq!(toolchain.name, "nightly-x86_64-test");
+    assert_eq!(
+        toolchain.source,
+        ToolchainSource::Override(overridden.clone())
+    );
+
+    let toolchain = rustup
+        .clone()
+        .cwd(&overridden)
+        .toolchain_override("stable")
+        .toolchain()
+        .await
+        .unwrap();
+    assert_eq!(toolchain.source, ToolchainSource::Environment);
+    assert_eq!(toolchain.dir, home.join("toolchains/stable-x86_64-test"));
+
+    let missing = rustup.clone().toolchain_override("beta").toolchain().await;
+    assert!(matches!(
+        missing,
+        Err(Error::ToolchainNotInstalled { name, .. }) if name == "beta-x86_64-test"
+    ));
+    assert!(matches!(
+        rustup.which("rustfmt").await,
+        Err(Error::CannotFindBinaryPath { .. })
+    ));
+
+    tokio::fs::write(pinned.join("rust-toolchain.toml"), "[toolchain]\n")
+        .await
+        .unwrap();
+    assert!(matches!(
+        Rustup::new(&home, &pinned).toolchain().await,
+        Err(Error::InvalidRustupConfig { .. })
+    ));
+    assert!(matches!(
+        Rustup::new(root.join("empty"), &project).toolchain().await,
+        Err(Error::ToolchainUnset { .. })
+    ));
+}
+
+#[tokio::test]
+#[cfg(unix)]
+async fn test_sysroot_tools() {
+    use async_which::Sysroot;
+
+    let f = TestFixture::new().await;
+    let root = f.tempdir.path().canonicalize().unwrap();
+    let toolchain = root.join("toolchain");
+    let host = format!("{}-unknown-test", std::env::consts::ARCH);
+    let bin = toolchain.join("lib/rustlib").join(&host).join("bin");
+    tokio::fs::create_dir_all(bin.join("gcc-ld")).await.unwrap();
+    tokio::fs::create_dir_all(toolchain.join("lib/rustlib/wasm32-unknown-unknown/lib"))
+        .await
+        .unwrap();
+    tokio::fs::create_dir_all(toolchain.join("bin"))
+        .await
+        .unwrap();
+    let rustc = f.mk_bin("toolchain/bin/rustc", "").await.unwrap();
+    let lld = f
+        .mk_bin(&format!("toolchain/lib/rustlib/{}/bin/rust-lld", host), "")
+        .await
+        .unwrap();
+    let ld_lld = f
+        .mk_bin(
+            &format!("toolchain/lib/rustlib/{}/bin/gcc-ld/ld", host),
+            "lld",
+        )
+        .await
+        .unwrap();
+    f.touch(
+        &format!("toolchain/lib/rustlib/{}/bin/llvm-objcopy", host),
+        "",
+    )
+    .await
+    .unwrap();
+
+    let link = root.join("a").join("rustc");
+    tokio::fs::symlink(&rustc, &link).await.unwrap();
+    let sysroot = Sysroot::from_rustc(&link).await.unwrap();
+    assert_eq!(sysroot.root(), toolchain.as_path());
+    assert_eq!(sysroot.host_triple().await, Some(host.clone()));
+
+    let search_path = sysroot.search_path().await;
+    let dirs: Vec<_> = search_path.dirs().collect();
+    assert_eq!(dirs, [bin.as_path(), bin.join("gcc-ld").as_path()]);
+
+    assert_eq!(sysroot.which("rust-lld").await.unwrap(), lld);
+    assert_eq!(sysroot.which("ld.lld").await.unwrap(), ld_lld);
+    assert!(matches!(
+        sysroot.which("llvm-objcopy").await,
+        Err(async_which::Error::NotExecutable { .. })
+    ));
+
+    let cross = Sysroot::new(&toolchain).host("wasm32-unknown-unknown");
+    assert!(matches!(
+        cross.which("rust-lld").await,
+        Err(async_which::Error::CannotFindBinaryPath { .. })
+    ));
+
+    // With several targets for one architecture, the recorded host wins over the sort order.
+    let sysroot_with = |name: &'static str, hosts: [&'static str; 2]| {
+        let root = root.join(name);
+        async move {
+            for host in hosts {
+                let bin = root.join("lib/rustlib").join(host).join("bin");
+                tokio::fs::create_dir_all(bin).await.unwrap();
+            }
+            Sysroot::new(root)
+        }
+    };
+    let recorded = sysroot_with(
+        "custom",
+        ["x86_64-pc-windows-gnu", "x86_64-unknown-linux-gnu"],
+    );
+    let recorded = recorded.await;
+    tokio::fs::write(
+        recorded.root().join("lib/rustlib/components"),
+        "rust-std-x86_64-pc-windows-gnu\nrustc-x86_64-unknown-linux-gnu\n",
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        recorded.host_triple().await.as_deref(),
+        Some("x86_64-unknown-linux-gnu")
+    );
+    let named = sysroot_with(
+        "stable-aarch64-unknown-linux-gnu",
+        ["aarch64-apple-darwin", "aarch64-unknown-linux-gnu"],
+    );
+    assert_eq!(
+        named.await.host_triple().await.as_deref(),
+        Some("aarch64-unknown-linux-gnu")
+    );
+    #[cfg(all(target_os = "linux", target_arch = "x86_64", target_env = "gnu"))]
+    {
+        let running = sysroot_with(
+            "plain",
+            ["x86_64-pc-windows-gnu", "x86_64-unknown-linux-gnu"],
+        );
+        assert_eq!(
+            running.await.host_triple().await.as_deref(),
+            Some("x86_64-unknown-linux-gnu")
+        );
+    }
+}
+
+#[tokio::test]
+#[cfg(unix)]
+async fn test_project_bins() {
+    use async_which::{ProjectBins, ProjectBoundary, WhichConfig};
+
+    let f = TestFixture::new().await;
+    let root = f.tempdir.path().canonicalize().unwrap();
+    let repo = root.join("repo");
+    let web = repo.join("packages/web");
+    let tools = repo.join("tools");
+    for dir in [
+        repo.join(".git"),
+        web.join("src"),
+        web.join("node_modules/.bin"),
+        repo.join("node_modules/.bin"),
+        root.join("node_modules/.bin"),
+        tools.join("bin"),
+    ] {
+        tokio::fs::create_dir_all(dir).await.unwrap();
+    }
+    tokio::fs::write(tools.join("Gemfile"), "").await.unwrap();
+    let web_eslint = f
+        .mk_bin("repo/packages/web/node_modules/.bin/eslint", "")
+        .await
+        .unwrap();
+    let repo_eslint = f.mk_bin("repo/node_modules/.bin/eslint", "").await.unwrap();
+    let prettier = f.mk_bin("node_modules/.bin/prettier", "").await.unwrap();
+    let rake = f.mk_bin("repo/tools/bin/rake", "").await.unwrap();
+
+    let config = || {
+        WhichConfig::new()
+            .custom_cwd(web.join("src"))
+            .custom_path_list(f.paths.clone())
+    };
+    let found = config()
+        .binary_name("eslint".into())
+        .project_bins(ProjectBins::new())
+        .first_result()
+        .await
+        .unwrap();
+    assert_eq!(found, web_eslint);
+
+    let all: Vec<_> = config()
+        .binary_name("eslint".into())
+        .project_bins(ProjectBins::new())
+        .all_sourced()
+        .map(|found| found.unwrap())
+        .collect()
+        .await;
+    let sources: Vec<_> = all.iter().map(|found| found.source().unwrap()).collect();
+    assert_eq!(all[1].path, repo_eslint);
+    assert_eq!(sources, ["project", "project"]);
+
+    let found = config()
+        .binary_name(BIN_NAME.into())
+        .project_bins(ProjectBins::new())
+        .first_sourced()
+        .await
+        .unwrap();
+    assert_eq!(found.path, f.bins[0]);
+    assert_eq!(found.source(), Some("custom"));
+
+    // The walk stops at the repository root by default.
+    let result = config()
+        .binary_name("prettier".into())
+        .project_bins(ProjectBins::new())
+        .first_result()
+        .await;
+    assert!(result.is_err());
+    let found = config()
+        .binary_name("prettier".into())
+        .project_bins(ProjectBins::new().boundary(ProjectBoundary::None))
+        .first_result()
+        .await
+        .unwrap();
+    assert_eq!(found, prettier);
+
+    let search_path = ProjectBins::new()
+        .boundary(ProjectBoundary::Dir(web.clone()))
+        .search_path(web.join("src"))
+        .await;
+    let dirs: Vec<_> = search_path.dirs().collect();
+    assert_eq!(dirs, [web.join("node_modules/.bin").as_path()]);
+
+    // The walk goes through the directory a symlinked working directory points at.
+    let web_link = root.join("web-link");
+    tokio::fs::symlink(&web, &web_link).await.unwrap();
+    let search_path = ProjectBins::new()
+        .boundary(ProjectBoundary::Dir(web_link.clone()))
+        .search_path(web_link.join("src"))
+        .await;
+    let dirs: Vec<_> = search_path.dirs().collect();
+    assert_eq!(dirs, [web.join("node_modules/.bin").as_path()]);
+
+    let marked = ProjectBins::empty().marked_dir("Gemfile", "bin");
+    assert!(marked.search_path(web.join("src")).await.is_empty());
+    let found = config()
+        .binary_name("rake".into())
+        .custom_cwd(tools.clone())
+        .project_bins(marked)
+        .first_result()
+        .await
+        .unwrap();
+    assert_eq!(found, rake);
+    let found = config()
+        .binary_name("rake".into())
+        .custom_cwd(tools.clone())
+        .project_bins(ProjectBins::new())
+        .first_sourced()
+        .await
+        .unwrap();
+    assert_eq!(found.path, rake);
+    assert_eq!(found.source(), Some("project"));
+}
+
+#[tokio::test]
+#[cfg(unix)]
+async fn test_python_envs() {
+    use async_which::{Python, PythonEnvKind, PythonEnvSource};
+
+    let f = TestFixture::new().await;
+    let root = f.tempdir.path().canonicalize().unwrap();
+    let project = root.join("project");
+    let venv = project.join(".venv");
+    for dir in [
+        project.join(".git"),
+        project.join("src"),
+        venv.join("bin"),
+        root.join("active/bin"),
+        root.join("conda/bin"),
+    ] {
+        tokio::fs::create_dir_all(dir).await.unwrap();
+    }
+    tokio::fs::write(venv.join("pyvenv.cfg"), "home = /usr/bin\n")
+        .await
+        .unwrap();
+    let pytest = f.mk_bin("project/.venv/bin/pytest", "").await.unwrap();
+    let python3 = f.mk_bin("project/.venv/bin/python3", "").await.unwrap();
+    f.mk_bin("a/python", "").await.unwrap();
+    let active_pytest = f.mk_bin("active/bin/pytest", "").await.unwrap();
+    let conda_python = f.mk_bin("conda/bin/python", "").await.unwrap();
+
+    let python = Python::new(project.join("src")).path_list(f.paths.clone());
+    let env = python.env().await.unwrap();
+    assert_eq!(env.prefix, venv);
+    assert_eq!(env.kind, PythonEnvKind::Virtualenv);
+    assert_eq!(env.source, PythonEnvSource::Discovered);
+    assert_eq!(
+        env.to_string(),
+        format!("virtualenv {} (discovered)", venv.display())
+    );
+
+    let found = python.which("pytest").await.unwrap();
+    assert_eq!(found.path, pytest);
+    assert_eq!(found.env, Some(env.clone()));
+    let found = python.which(BIN_NAME).await.unwrap();
+    assert_eq!(found.path, f.bins[0]);
+    assert_eq!(found.env, None);
+
+    // The environment's python3 wins over the python on PATH.
+    let interpreter = python.interpreter().await.unwrap();
+    assert_eq!(interpreter.path, python3);
+    assert!(interpreter.env.is_some());
+    let found = python.clone().discover(false).interpreter().await.unwrap();
+    assert_eq!(found.path, root.join("a/python"));
+    assert_eq!(found.env, None);
+
+    let conda = python.clone().conda_prefix(root.join("conda"));
+    let found = conda.interpreter().await.unwrap();
+    assert_eq!(found.path, conda_python);
+    let env = found.env.unwrap();
+    assert_eq!(env.kind, PythonEnvKind::Conda);
+    assert_eq!(env.source, PythonEnvSource::CondaPrefixVar);
+
+    let active = conda.virtual_env(root.join("active"));
+    let found = active.which("pytest").await.unwrap();
+    assert_eq!(found.path, active_pytest);
+    assert_eq!(found.env.unwrap().source, PythonEnvSource::VirtualEnvVar);
+
+    assert_eq!(Python::new(&root).env().await, None);
+}